@@ -0,0 +1,98 @@
+//! Thumbnail throughput on large JPEGs and TIFFs.
+//!
+//! Compares a full `image::open` + `thumbnail` against the scaled decode in
+//! `thumb::decode`. Point `PROOF_BENCH_DIR` at a folder of real files, or
+//! leave it unset to benchmark against generated 24-megapixel images.
+//!
+//!     cargo bench --bench thumbnails
+//!     PROOF_BENCH_DIR=~/finals cargo bench --bench thumbnails
+
+use std::path::{Path, PathBuf};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use proof::thumb;
+
+const TARGET: u32 = 300;
+
+fn main() {
+    let generated;
+    let dir = match std::env::var_os("PROOF_BENCH_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            generated = tempfile::tempdir().expect("tempdir");
+            generate(generated.path());
+            generated.path().to_path_buf()
+        }
+    };
+
+    for ext in ["jpg", "tif"] {
+        let files = collect(&dir, ext);
+        if files.is_empty() {
+            continue;
+        }
+        let bytes: u64 = files
+            .iter()
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+
+        let full = time(&files, |p| {
+            black_box(image::open(p).expect("decode").thumbnail(TARGET, TARGET));
+        });
+        let fast = time(&files, |p| {
+            black_box(thumb::decode(p, TARGET).expect("decode").image.thumbnail(TARGET, TARGET));
+        });
+
+        println!("{} x {} ({} MiB)", files.len(), ext, bytes / (1024 * 1024));
+        report("  full decode  ", files.len(), bytes, full);
+        report("  thumb::decode", files.len(), bytes, fast);
+    }
+}
+
+fn time(files: &[PathBuf], f: impl Fn(&Path)) -> Duration {
+    let start = Instant::now();
+    for path in files {
+        f(path);
+    }
+    start.elapsed()
+}
+
+fn report(label: &str, count: usize, bytes: u64, elapsed: Duration) {
+    let secs = elapsed.as_secs_f64();
+    println!(
+        "{}  {:>8.2?}  {:>6.1} files/s  {:>7.1} MiB/s",
+        label,
+        elapsed,
+        count as f64 / secs,
+        bytes as f64 / (1024.0 * 1024.0) / secs,
+    );
+}
+
+fn collect(dir: &Path, ext: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .expect("read bench dir")
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case(ext) || (ext == "jpg" && e.eq_ignore_ascii_case("jpeg")))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn generate(dir: &Path) {
+    let (w, h) = (6000, 4000);
+    for i in 0..4u32 {
+        let img = image::RgbImage::from_fn(w, h, |x, y| {
+            let n = (x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503) ^ i) >> 27;
+            image::Rgb([(x * 255 / w) as u8 ^ n as u8, (y * 255 / h) as u8, ((x + y) % 256) as u8])
+        });
+        img.save(dir.join(format!("{i:02}.jpg"))).expect("write jpeg");
+        if i < 2 {
+            img.save(dir.join(format!("{i:02}.tif"))).expect("write tiff");
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// Shared flag for stopping background work. Clones observe the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+/// Error returned by work that stopped because it was cancelled.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Fail with `Cancelled` once `cancel` has been called.
+    pub fn check(&self) -> std::result::Result<(), Cancelled> {
+        if self.is_cancelled() {
+            return Err(Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Run `cmd` to completion, killing it if cancelled in the meantime.
+    pub fn status(&self, cmd: &mut Command) -> Result<ExitStatus> {
+        let mut child = cmd.spawn()?;
+        self.wait(&mut child)
+    }
+
+    /// Like `status`, but capture stdout and stderr.
+    pub fn output(&self, cmd: &mut Command) -> Result<Output> {
+        let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+
+        // Drain both pipes on other threads so a chatty child can't block on
+        // a full pipe while we poll it.
+        let stdout = drain(child.stdout.take().context("stdout not captured")?);
+        let stderr = drain(child.stderr.take().context("stderr not captured")?);
+
+        let status = self.wait(&mut child)?;
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        Ok(Output { status, stdout, stderr })
+    }
+
+    fn wait(&self, child: &mut Child) -> Result<ExitStatus> {
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            if self.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(Cancelled.into());
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+fn drain(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// Whether `err` (or anything in its chain) is a cancellation.
+pub fn is_cancelled(err: &anyhow::Error) -> bool {
+    use crate::error::{ProcessError, RenderError};
+    err.chain().any(|e| {
+        e.is::<Cancelled>()
+            || matches!(e.downcast_ref(), Some(ProcessError::Cancelled))
+            || matches!(e.downcast_ref(), Some(RenderError::Cancelled))
+    })
+}
@@ -0,0 +1,296 @@
+//! What changed since a previous delivery, from the JSON manifest written
+//! for it.
+//!
+//! Files are matched on the manifest's `path` column, or `filename` when
+//! there isn't one. A `checksum` column tells replaced and renamed files
+//! apart from unchanged ones, with `bytes` as a rougher fallback, and a
+//! manifest with neither is refused. A `thumbnail` column lets replacements
+//! be shown next to what they replace:
+//!
+//! ```sh
+//! proof ./finals --manifest-out v1.json --manifest-format json \
+//!     --fields path,resolution,size,bytes,checksum,thumbnail
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::scan::Asset;
+
+/// A file as the previous manifest lists it.
+#[derive(Debug, Clone)]
+pub struct Listed {
+    /// Position in the manifest.
+    pub index: usize,
+    /// Its `path`, or its `filename` in manifests without paths.
+    pub name: String,
+    pub checksum: Option<String>,
+    pub bytes: Option<u64>,
+    pub resolution: Option<String>,
+    /// JPEG bytes of its thumbnail.
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+/// A previous delivery's manifest.
+#[derive(Debug, Clone)]
+pub struct Previous {
+    /// What the delivery is called in "Changes since …", from the
+    /// manifest's file name.
+    pub label: String,
+    pub files: Vec<Listed>,
+    /// Whether files are named by path rather than filename.
+    by_path: bool,
+}
+
+#[derive(Deserialize)]
+struct Row {
+    path: Option<String>,
+    filename: Option<String>,
+    checksum: Option<String>,
+    bytes: Option<u64>,
+    resolution: Option<String>,
+    thumbnail: Option<String>,
+}
+
+impl Previous {
+    /// Read a manifest written with `--manifest-format json`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read '{}'", path.display()))?;
+        let rows: Vec<Row> = serde_json::from_str(&text).with_context(|| {
+            format!("'{}' isn't a JSON manifest (write one with --manifest-format json)", path.display())
+        })?;
+
+        // Without either, every file with the same name looks unchanged
+        if !rows.is_empty() && rows.iter().all(|r| r.checksum.is_none() && r.bytes.is_none()) {
+            bail!(
+                "'{}' has no checksum or bytes column, so changed files can't be told apart \
+                 (write it with --fields path,bytes,checksum,thumbnail)",
+                path.display()
+            );
+        }
+        let by_path = rows.iter().all(|r| r.path.is_some());
+        let mut files = Vec::with_capacity(rows.len());
+        for (index, row) in rows.into_iter().enumerate() {
+            let name = match (by_path, row.path, row.filename) {
+                (true, Some(path), _) => path,
+                (false, _, Some(filename)) => filename,
+                // A path without a filename column still ends in one
+                (false, Some(path), None) => path.rsplit('/').next().unwrap_or(&path).to_string(),
+                _ => bail!("'{}' lists a file without a path or filename", path.display()),
+            };
+            let thumbnail = row.thumbnail.as_deref().and_then(|uri| {
+                let data = uri.strip_prefix("data:image/jpeg;base64,")?;
+                base64::engine::general_purpose::STANDARD.decode(data).ok()
+            });
+            files.push(Listed {
+                index,
+                name,
+                checksum: row.checksum,
+                bytes: row.bytes,
+                resolution: row.resolution,
+                thumbnail,
+            });
+        }
+
+        let label = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("last delivery"));
+        Ok(Self { label, files, by_path })
+    }
+}
+
+/// The difference between a previous delivery and the assets now.
+#[derive(Debug, Clone)]
+pub struct Changes {
+    /// What the previous delivery is called.
+    pub since: String,
+    /// Positions in the current assets.
+    pub added: Vec<usize>,
+    pub removed: Vec<Listed>,
+    /// Same name, different contents, with what used to be there.
+    pub replaced: Vec<(usize, Listed)>,
+    /// Same contents under a new name, with the old one.
+    pub renamed: Vec<(usize, Listed)>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.replaced.is_empty() && self.renamed.is_empty()
+    }
+
+    /// "2 added, 1 replaced", or "no changes".
+    pub fn describe(&self) -> String {
+        let counts = [
+            (self.added.len(), "added"),
+            (self.removed.len(), "removed"),
+            (self.replaced.len(), "replaced"),
+            (self.renamed.len(), "renamed"),
+        ];
+        let parts: Vec<String> = counts
+            .iter()
+            .filter(|(n, _)| *n > 0)
+            .map(|(n, what)| format!("{n} {what}"))
+            .collect();
+        if parts.is_empty() {
+            String::from("no changes")
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Compare `assets`, found under `root`, with what `previous` listed.
+pub fn compare(previous: &Previous, assets: &[Asset], root: &Path) -> Changes {
+    let name = |a: &Asset| {
+        if previous.by_path {
+            crate::config::relative(root, &a.path)
+        } else {
+            a.filename.clone()
+        }
+    };
+    let mut unmatched: HashMap<&str, &Listed> = previous.files.iter().map(|f| (f.name.as_str(), f)).collect();
+
+    let mut changes = Changes {
+        since: previous.label.clone(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        replaced: Vec::new(),
+        renamed: Vec::new(),
+    };
+    let mut new = Vec::new();
+    for (index, asset) in assets.iter().enumerate() {
+        let Some(listed) = unmatched.remove(name(asset).as_str()) else {
+            new.push(index);
+            continue;
+        };
+        let differs = match (&listed.checksum, &asset.checksum) {
+            (Some(old), Some(now)) => old != now,
+            _ => listed.bytes.is_some_and(|b| b != asset.file_size),
+        };
+        if differs {
+            changes.replaced.push((index, listed.clone()));
+        }
+    }
+
+    // A new name with contents that went missing from an old one was moved.
+    // Look in manifest order, so repeated checksums pair up the same way
+    // every time.
+    for index in new {
+        let moved = assets[index].checksum.as_ref().and_then(|sum| {
+            let old = previous
+                .files
+                .iter()
+                .find(|f| f.checksum.as_ref() == Some(sum) && unmatched.contains_key(f.name.as_str()))?;
+            unmatched.remove(old.name.as_str())
+        });
+        match moved {
+            Some(listed) => changes.renamed.push((index, listed.clone())),
+            None => changes.added.push(index),
+        }
+    }
+
+    let mut removed: Vec<Listed> = unmatched.into_values().cloned().collect();
+    removed.sort_by_key(|f| f.index);
+    changes.removed = removed;
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::AssetKind;
+
+    fn asset(path: &str, checksum: &str) -> Asset {
+        let mut asset = Asset::stub(&Path::new("/root").join(path), AssetKind::Image);
+        asset.checksum = Some(checksum.to_string());
+        asset
+    }
+
+    fn listed(index: usize, name: &str, checksum: Option<&str>, bytes: Option<u64>) -> Listed {
+        Listed {
+            index,
+            name: name.to_string(),
+            checksum: checksum.map(str::to_string),
+            bytes,
+            resolution: None,
+            thumbnail: None,
+        }
+    }
+
+    fn previous(files: Vec<Listed>) -> Previous {
+        Previous { label: String::from("v1"), files, by_path: true }
+    }
+
+    fn names(files: &[Listed]) -> Vec<&str> {
+        files.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn classifies_every_kind_of_change() {
+        let before = previous(vec![
+            listed(0, "same.jpg", Some("a"), None),
+            listed(1, "edited.jpg", Some("b"), None),
+            listed(2, "old-name.jpg", Some("c"), None),
+            listed(3, "gone.jpg", Some("d"), None),
+        ]);
+        let now = [asset("same.jpg", "a"), asset("edited.jpg", "b2"), asset("new-name.jpg", "c"), asset("new.jpg", "e")];
+        let changes = compare(&before, &now, Path::new("/root"));
+
+        assert_eq!(changes.added, [3]);
+        assert_eq!(names(&changes.removed), ["gone.jpg"]);
+        assert_eq!(changes.replaced.len(), 1);
+        assert_eq!(changes.replaced[0].0, 1);
+        assert_eq!(changes.renamed.len(), 1);
+        assert_eq!((changes.renamed[0].0, changes.renamed[0].1.name.as_str()), (2, "old-name.jpg"));
+        assert_eq!(changes.describe(), "1 added, 1 removed, 1 replaced, 1 renamed");
+    }
+
+    #[test]
+    fn unchanged_delivery_has_no_changes() {
+        let before = previous(vec![listed(0, "a.jpg", Some("a"), None), listed(1, "sub/b.jpg", Some("b"), None)]);
+        let changes = compare(&before, &[asset("a.jpg", "a"), asset("sub/b.jpg", "b")], Path::new("/root"));
+        assert!(changes.is_empty());
+        assert_eq!(changes.describe(), "no changes");
+    }
+
+    #[test]
+    fn falls_back_to_bytes_without_checksums() {
+        let before = previous(vec![listed(0, "a.jpg", None, Some(10)), listed(1, "b.jpg", None, Some(0))]);
+        let changes = compare(&before, &[asset("a.jpg", "a"), asset("b.jpg", "b")], Path::new("/root"));
+        // Placeholders read the size of a file that isn't there as 0
+        assert_eq!(changes.replaced.len(), 1);
+        assert_eq!(changes.replaced[0].0, 0);
+    }
+
+    #[test]
+    fn renames_pair_up_in_manifest_order() {
+        let before = previous(vec![
+            listed(0, "first.jpg", Some("x"), None),
+            listed(1, "second.jpg", Some("x"), None),
+        ]);
+        for _ in 0..10 {
+            let changes = compare(&before, &[asset("moved.jpg", "x")], Path::new("/root"));
+            assert_eq!(changes.renamed[0].1.name, "first.jpg");
+            assert_eq!(names(&changes.removed), ["second.jpg"]);
+        }
+    }
+
+    #[test]
+    fn refuses_manifests_that_cant_show_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v1.json");
+        std::fs::write(&path, r#"[{"filename": "a.jpg", "kind": "Image"}]"#).unwrap();
+        assert!(Previous::load(&path).is_err());
+
+        std::fs::write(&path, r#"[{"path": "a.jpg", "bytes": 10}]"#).unwrap();
+        let previous = Previous::load(&path).unwrap();
+        assert_eq!(previous.label, "v1");
+        assert_eq!(names(&previous.files), ["a.jpg"]);
+    }
+}
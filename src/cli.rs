@@ -1,41 +1,269 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Parser)]
-#[command(name = "proof", version, about = "Branded delivery proof generator")]
+use proof::config::Config;
+use proof::manifest::{Column, Format, Manifest};
+use proof::scan::ProcessOptions;
+use proof::{OutputFields, OutputTemplate};
+
+#[derive(Clone, Parser)]
+#[command(
+    name = "proof",
+    version,
+    about = "Branded delivery proof generator",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Directory containing delivery assets
-    pub input: PathBuf,
+    #[arg(required = true)]
+    input: Option<PathBuf>,
 
     /// Client name (appears on cover page)
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub client: Option<String>,
 
     /// Document title
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub title: Option<String>,
 
     /// Delivery date (defaults to today)
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub date: Option<String>,
 
     /// Grid columns for contact sheet (3-8)
-    #[arg(long, default_value = "4", value_parser = clap::value_parser!(u8).range(3..=8))]
+    #[arg(long, global = true, default_value = "4", value_parser = clap::value_parser!(u8).range(3..=8))]
     pub columns: u8,
 
     /// Output PDF file path
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub output: Option<PathBuf>,
 
+    /// Output name built from {client}, {title}, {date}, {rev}, {files}
+    /// and {folder}, with a number added rather than overwriting
+    #[arg(long, global = true, value_name = "TEMPLATE", conflicts_with = "output")]
+    pub output_template: Option<OutputTemplate>,
+
+    /// Also write a static HTML gallery into this folder
+    #[arg(long, global = true, value_name = "DIR")]
+    pub html: Option<PathBuf>,
+
+    /// Also package the assets, PDF and a manifest into this ZIP
+    #[arg(long, global = true, value_name = "ZIP", conflicts_with = "manifest_only")]
+    pub package: Option<PathBuf>,
+
+    /// README to include in the --package ZIP
+    #[arg(long, global = true, value_name = "FILE", requires = "package")]
+    pub package_readme: Option<PathBuf>,
+
+    /// Thumbnail size in pixels (defaults to ~200dpi at the column width)
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(64..=4096))]
+    pub thumb_size: Option<u32>,
+
+    /// JPEG quality for thumbnails (1-100)
+    #[arg(long, global = true, default_value = "90", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub thumb_quality: u8,
+
+    /// Frames per video for filmstrip pages (0 to disable)
+    #[arg(long, global = true, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=12))]
+    pub filmstrip: u8,
+
     /// Auto-rotate thumbnails using EXIF orientation
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub auto_orient: bool,
 
+    /// Worker threads for processing (defaults to one per CPU core)
+    #[arg(short, long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+
+    /// JSON manifest of a previous delivery, to add a page of what changed
+    /// since
+    #[arg(long, global = true, value_name = "MANIFEST")]
+    pub previous: Option<PathBuf>,
+
+    /// Leave byte-for-byte copies of a file off the contact sheet
+    #[arg(long, global = true)]
+    pub skip_duplicates: bool,
+
+    /// Fully decode every image and video to catch truncated or corrupt
+    /// files, and add a QC section to the proof
+    #[arg(long, global = true)]
+    pub deep_check: bool,
+
     /// Only output asset manifest to stdout (skip PDF)
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub manifest_only: bool,
 
+    /// Manifest format (defaults to the --manifest-out extension, then tsv)
+    #[arg(long, global = true, value_enum)]
+    pub manifest_format: Option<Format>,
+
+    /// Also write the manifest to this file
+    #[arg(long, global = true)]
+    pub manifest_out: Option<PathBuf>,
+
+    /// Manifest columns, comma-separated
+    /// [default: filename,kind,resolution,format,size,color-space]
+    #[arg(long, global = true, value_enum, value_delimiter = ',')]
+    pub fields: Option<Vec<Column>>,
+
     /// Disable TUI dashboard (use plain text output)
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub no_tui: bool,
+
+    /// Render straight after processing, skipping the TUI review step
+    #[arg(long, global = true)]
+    pub no_review: bool,
+
+    /// Progress output on stderr. `json` prints one event per line and
+    /// implies --no-tui
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    pub progress: ProgressFormat,
+
+    /// Terminal graphics protocol for TUI thumbnail previews
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    pub graphics: Graphics,
+}
+
+#[derive(Clone, Subcommand)]
+pub enum Command {
+    /// Re-render the proof whenever files in the directory change
+    Watch {
+        /// Directory containing delivery assets
+        input: PathBuf,
+
+        /// Seconds without changes before re-rendering, up to 3600
+        #[arg(long, default_value = "2", value_parser = parse_debounce)]
+        debounce: Duration,
+    },
+    /// Render the proof with a page of what changed since a previous
+    /// delivery
+    Diff {
+        /// JSON manifest of the previous delivery
+        previous: PathBuf,
+
+        /// Directory containing delivery assets
+        input: PathBuf,
+    },
+}
+
+impl Cli {
+    /// Directory containing delivery assets, whichever way it was given.
+    pub fn input(&self) -> &Path {
+        match &self.command {
+            Some(Command::Watch { input, .. } | Command::Diff { input, .. }) => input,
+            None => self.input.as_deref().expect("input is required without a subcommand"),
+        }
+    }
+
+    /// Manifest of the delivery to compare with, from `diff` or
+    /// `--previous`.
+    pub fn previous(&self) -> Option<&Path> {
+        match &self.command {
+            Some(Command::Diff { previous, .. }) => Some(previous),
+            _ => self.previous.as_deref(),
+        }
+    }
+
+    pub fn is_watch(&self) -> bool {
+        matches!(self.command, Some(Command::Watch { .. }))
+    }
+
+    /// Processing settings from the flags and `project`. Manifest-only runs
+    /// skip thumbnails, and checksums unless the manifest lists them.
+    pub fn process_options(&self, project: &Config) -> ProcessOptions {
+        let mut opts = ProcessOptions::default();
+        opts.gen_thumbnails = !self.manifest_only;
+        opts.auto_orient = self.auto_orient;
+        opts.thumb_size = self.thumb_size.unwrap_or_else(|| proof::pdf::default_thumb_size(self.columns));
+        opts.thumb_quality = self.thumb_quality;
+        opts.posters = project.poster_paths(self.input());
+        opts.filmstrip = self.filmstrip;
+        opts.deep_check = self.deep_check;
+        opts.checksums = !self.manifest_only || self.manifest().columns.contains(&Column::Checksum);
+        opts
+    }
+
+    /// Client, title and date from the flags, then `project`, then
+    /// "Delivery" and today.
+    pub fn details(&self, project: &Config) -> Details {
+        Details {
+            client: self
+                .client
+                .clone()
+                .or_else(|| project.client.clone())
+                .unwrap_or_else(|| String::from("Delivery")),
+            title: self.title.clone().or_else(|| project.title.clone()),
+            date: self
+                .date
+                .clone()
+                .or_else(|| project.date.clone())
+                .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string()),
+        }
+    }
+
+    /// `--output-template`, or the default.
+    pub fn template(&self) -> OutputTemplate {
+        self.output_template.clone().unwrap_or_default()
+    }
+
+    /// From `--manifest-format`, `--fields` and `--manifest-out`. The
+    /// format defaults to the output file's extension, then TSV.
+    pub fn manifest(&self) -> Manifest {
+        let out = self.manifest_out.clone();
+        let format = self
+            .manifest_format
+            .or_else(|| out.as_deref().and_then(Format::from_path))
+            .unwrap_or(Format::Tsv);
+        let columns = match self.fields {
+            Some(ref fields) => fields.clone(),
+            None if format == Format::Json => Column::JSON_DEFAULT.to_vec(),
+            None => Column::DEFAULT.to_vec(),
+        };
+        Manifest { format, columns, out }
+    }
+}
+
+/// Who and what the proof is for, as printed on it.
+pub struct Details {
+    pub client: String,
+    pub title: Option<String>,
+    pub date: String,
+}
+
+impl Details {
+    /// What goes into the output name template.
+    pub fn fields<'a>(&'a self, revision: u32, files: usize, input: &'a Path) -> OutputFields<'a> {
+        OutputFields { client: &self.client, title: self.title.as_deref(), date: &self.date, revision, files, input }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ProgressFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Graphics {
+    /// Detect from the terminal environment
+    Auto,
+    Kitty,
+    Iterm,
+    Sixel,
+    /// Unicode half blocks, for any colour terminal
+    Blocks,
+}
+
+/// Whole or fractional seconds, from 0 to an hour.
+fn parse_debounce(s: &str) -> Result<Duration, String> {
+    let secs: f64 = s.parse().map_err(|_| format!("'{s}' isn't a number of seconds"))?;
+    if !(0.0..=3600.0).contains(&secs) {
+        return Err(format!("{s} is not in 0..=3600"));
+    }
+    Ok(Duration::from_secs_f64(secs))
 }
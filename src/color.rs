@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use image::{DynamicImage, GenericImageView};
+use moxcms::{ColorProfile, DataColorSpace, Layout, ProfileText, TransformOptions};
+
+/// Human-readable name of an embedded ICC profile, e.g. "Adobe RGB (1998)".
+pub fn describe(icc: &[u8]) -> Option<String> {
+    let profile = ColorProfile::new_from_slice(icc).ok()?;
+    let text = match profile.description? {
+        ProfileText::PlainString(s) => s,
+        ProfileText::Localizable(strings) => strings
+            .iter()
+            .find(|s| s.language == "en")
+            .or_else(|| strings.first())
+            .map(|s| s.value.clone())?,
+        ProfileText::Description(d) => d.ascii_string,
+    };
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Convert an RGB or grayscale image from its embedded profile to sRGB.
+/// Profiles that don't describe the decoded pixels (e.g. a CMYK profile on
+/// a TIFF that the decoder already flattened to RGB) are left alone.
+pub fn to_srgb(image: DynamicImage, icc: &[u8]) -> Result<DynamicImage> {
+    let source = ColorProfile::new_from_slice(icc).context("cannot parse ICC profile")?;
+    let (w, h) = image.dimensions();
+
+    let (layout, pixels) = match source.color_space {
+        DataColorSpace::Rgb => (Layout::Rgb, image.to_rgb8().into_raw()),
+        DataColorSpace::Gray => (Layout::Gray, image.to_luma8().into_raw()),
+        _ => return Ok(image),
+    };
+
+    let rgb = transform(&source, layout, &pixels, (w * h) as usize)?;
+    image::RgbImage::from_raw(w, h, rgb)
+        .map(DynamicImage::ImageRgb8)
+        .context("unexpected pixel buffer size")
+}
+
+/// Convert 8-bit CMYK samples (0 = no ink) to sRGB, through the embedded
+/// profile when there is one and a naive conversion otherwise.
+pub fn cmyk_to_srgb(cmyk: &[u8], width: u32, height: u32, icc: Option<&[u8]>) -> Result<DynamicImage> {
+    let profile = icc
+        .and_then(|icc| ColorProfile::new_from_slice(icc).ok())
+        .filter(|p| p.color_space == DataColorSpace::Cmyk);
+
+    let rgb = match profile {
+        Some(source) => transform(&source, Layout::Rgba, cmyk, (width * height) as usize)?,
+        None => cmyk
+            .chunks_exact(4)
+            .flat_map(|p| {
+                let k = 255 - p[3] as u32;
+                [p[0], p[1], p[2]].map(|c| ((255 - c as u32) * k / 255) as u8)
+            })
+            .collect(),
+    };
+
+    image::RgbImage::from_raw(width, height, rgb)
+        .map(DynamicImage::ImageRgb8)
+        .context("unexpected pixel buffer size")
+}
+
+fn transform(source: &ColorProfile, layout: Layout, pixels: &[u8], count: usize) -> Result<Vec<u8>> {
+    let srgb = ColorProfile::new_srgb();
+    let executor = source
+        .create_transform_8bit(layout, &srgb, Layout::Rgb, TransformOptions::default())
+        .context("unsupported ICC profile")?;
+
+    let mut rgb = vec![0u8; count * 3];
+    executor
+        .transform(pixels, &mut rgb)
+        .context("colour conversion failed")?;
+    Ok(rgb)
+}
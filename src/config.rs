@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::scan::AssetKind;
+
+/// Name of the per-project settings file, read from the input directory.
+pub const FILENAME: &str = "proof.toml";
+
+/// Settings from `proof.toml` in the input directory. Command-line flags
+/// take precedence over the cover fields here.
+///
+/// ```toml
+/// client = "Armani"
+/// title = "SS26 Campaign"
+/// hero = "looks/look-01.jpg"
+/// exclude = ["outtakes/alt-03.jpg"]
+/// order = ["looks/look-02.jpg", "looks/look-01.jpg"]
+///
+/// [posters]
+/// "spots/spot-30s.mov" = 12.5   # poster frame at 12.5 seconds
+/// ```
+///
+/// Paths in `hero`, `exclude`, `order` and `posters` are relative to the
+/// input directory. Files missing from `order` follow in natural order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Image shown on the cover page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hero: Option<String>,
+    /// Files left out of the proof.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Contact sheet and manifest order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    /// Poster frame timestamps in seconds, keyed by video path.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub posters: HashMap<String, f64>,
+}
+
+impl Config {
+    /// Load `proof.toml` from `dir`, or defaults if there isn't one.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(FILENAME);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("cannot read '{}'", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("invalid '{}'", path.display()))
+    }
+
+    /// Write the settings that differ from `original` to `proof.toml` in
+    /// `dir`, leaving everything else in the file, comments included, as
+    /// it was. Settings back at their defaults are removed. Returns whether
+    /// anything was written.
+    pub fn save_changes(&self, dir: &Path, original: &Config) -> Result<bool> {
+        if self == original {
+            return Ok(false);
+        }
+        let path = dir.join(FILENAME);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).with_context(|| format!("cannot read '{}'", path.display())),
+        };
+        let mut doc: toml_edit::DocumentMut =
+            text.parse().with_context(|| format!("invalid '{}'", path.display()))?;
+
+        let now = toml_edit::ser::to_document(self)?;
+        let changed = [
+            ("client", self.client != original.client),
+            ("title", self.title != original.title),
+            ("date", self.date != original.date),
+            ("hero", self.hero != original.hero),
+            ("exclude", self.exclude != original.exclude),
+            ("order", self.order != original.order),
+            ("posters", self.posters != original.posters),
+        ];
+        for (key, _) in changed.into_iter().filter(|&(_, changed)| changed) {
+            match now.get(key) {
+                Some(item) => doc[key] = item.clone(),
+                None => {
+                    doc.remove(key);
+                }
+            }
+        }
+        std::fs::write(&path, doc.to_string()).with_context(|| format!("cannot write '{}'", path.display()))?;
+        Ok(true)
+    }
+
+    /// Reorder discovered files to match `order`, keeping the rest in their
+    /// existing order after the listed ones.
+    pub fn apply_order(&self, root: &Path, found: &mut [(PathBuf, AssetKind)]) {
+        if self.order.is_empty() {
+            return;
+        }
+        let rank: HashMap<&str, usize> = self
+            .order
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        found.sort_by_key(|(path, _)| {
+            rank.get(relative(root, path).as_str())
+                .copied()
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    pub fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        let rel = relative(root, path);
+        self.exclude.contains(&rel)
+    }
+
+    pub fn is_hero(&self, root: &Path, path: &Path) -> bool {
+        self.hero.as_deref() == Some(relative(root, path).as_str())
+    }
+
+    /// `posters` keyed by where the videos are under `root`, as
+    /// `ProcessOptions::posters` takes them.
+    pub fn poster_paths(&self, root: &Path) -> HashMap<PathBuf, f64> {
+        self.posters.iter().map(|(rel, &at)| (root.join(rel), at)).collect()
+    }
+}
+
+/// `path` relative to `root` with forward slashes, as written in `proof.toml`.
+pub fn relative(root: &Path, path: &Path) -> String {
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,105 @@
+//! The delivery log: a record in the project folder of every proof
+//! rendered from it, so each delivery gets the next revision number and
+//! the same day's deliveries don't overwrite each other.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::scan::Asset;
+
+/// Name of the log in the project folder.
+pub const FILENAME: &str = "proof-log.json";
+
+/// One rendered delivery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Counts up from 1.
+    pub revision: u32,
+    /// The date printed on the proof.
+    pub date: String,
+    /// When it was rendered, in RFC 3339.
+    pub rendered_at: String,
+    pub client: String,
+    pub files: usize,
+    pub output: PathBuf,
+    /// SHA-256 of each file, keyed by its path in the project folder.
+    pub checksums: BTreeMap<String, String>,
+}
+
+impl Delivery {
+    /// Revision `revision` of `assets`, found under `root` and rendered to
+    /// `output` just now.
+    pub fn new(revision: u32, client: &str, date: &str, assets: &[Asset], root: &Path, output: &Path) -> Self {
+        Self {
+            revision,
+            date: date.to_string(),
+            rendered_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            client: client.to_string(),
+            files: assets.len(),
+            output: std::fs::canonicalize(output).unwrap_or_else(|_| output.to_path_buf()),
+            checksums: assets
+                .iter()
+                .filter_map(|a| Some((crate::config::relative(root, &a.path), a.checksum.clone()?)))
+                .collect(),
+        }
+    }
+}
+
+/// Every delivery rendered from a project folder, oldest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Log {
+    pub deliveries: Vec<Delivery>,
+}
+
+impl Log {
+    /// Read the log in `dir`, or start an empty one if there isn't one yet.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(FILENAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("cannot read '{}'", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("invalid '{}'", path.display()))
+    }
+
+    /// Write back to the log in `dir`.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(FILENAME);
+        let text = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, text + "\n").with_context(|| format!("cannot write '{}'", path.display()))
+    }
+
+    /// The revision number the next delivery gets.
+    pub fn next_revision(&self) -> u32 {
+        self.deliveries.iter().map(|d| d.revision).max().unwrap_or(0) + 1
+    }
+
+    /// Add `delivery`, replacing an earlier entry for the same revision, as
+    /// when watch mode renders it again.
+    pub fn record(&mut self, delivery: Delivery) {
+        self.deliveries.retain(|d| d.revision != delivery.revision);
+        self.deliveries.push(delivery);
+        self.deliveries.sort_by_key(|d| d.revision);
+    }
+}
+
+/// Load the log in `dir`, add `delivery` and save it again.
+pub fn record(dir: &Path, delivery: Delivery) -> Result<()> {
+    let mut log = Log::load(dir)?;
+    log.record(delivery);
+    log.save(dir)
+}
+
+/// Like `record`, but for after the proof is written, when a log that
+/// can't be updated, as in a read-only folder, shouldn't fail the delivery.
+/// Returns what went wrong, to show as a warning.
+pub fn record_or_warn(dir: &Path, delivery: Delivery) -> Option<String> {
+    let revision = delivery.revision;
+    record(dir, delivery)
+        .err()
+        .map(|e| format!("delivery {revision} wasn't logged, so the next one gets the same number: {e:#}"))
+}
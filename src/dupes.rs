@@ -0,0 +1,210 @@
+//! Find the same picture delivered more than once: byte-for-byte copies
+//! under different names, and re-exports of an image at another size or
+//! quality.
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::cancel::Cancel;
+use crate::scan::{Asset, AssetKind};
+
+/// Perceptual hashes this many bits apart or fewer count as the same
+/// picture. Resizing and recompressing moves a few bits; different frames
+/// of a shoot are usually 15 or more apart.
+const SIMILAR_BITS: u32 = 6;
+
+/// How closely the files in a group match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Match {
+    /// Every file has the same contents.
+    Identical,
+    /// The pictures look the same, but the files differ.
+    Similar,
+}
+
+impl Match {
+    pub fn label(self) -> &'static str {
+        match self {
+            Match::Identical => "identical",
+            Match::Similar => "similar",
+        }
+    }
+}
+
+/// Files that are the same picture.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub matched: Match,
+    /// Positions in the assets given to `find`, in that order.
+    pub members: Vec<usize>,
+    /// Members with the same contents as an earlier one, paired with it.
+    /// These are what leaving duplicates out drops. Similar pictures always
+    /// stay: perceptual matches chain from file to file, and a false one
+    /// would take a real final off the proof.
+    pub copies: Vec<(usize, usize)>,
+}
+
+/// Hex SHA-256 of the contents of `path`. Fails with `Cancelled` once
+/// `cancel` fires, even partway through a large file.
+pub fn checksum(path: &Path, cancel: &Cancel) -> Result<String> {
+    hashed_copy(&mut std::fs::File::open(path)?, &mut std::io::sink(), cancel)
+}
+
+/// Copy `reader` to `writer` a chunk at a time, checking `cancel` between
+/// chunks, and return the hex SHA-256 of what passed through.
+pub fn hashed_copy(reader: &mut impl Read, writer: &mut impl Write, cancel: &Cancel) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1 << 20];
+    loop {
+        cancel.check()?;
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Group the assets that duplicate each other. Files with the same
+/// checksum always match; images also match on their perceptual hash.
+/// Unreadable placeholders are left out.
+pub fn find<'a>(assets: impl IntoIterator<Item = &'a Asset>) -> Vec<Group> {
+    let assets: Vec<&Asset> = assets.into_iter().collect();
+    let mut parent: Vec<usize> = (0..assets.len()).collect();
+
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let readable = |a: &Asset| a.error.is_none();
+    for i in 0..assets.len() {
+        for j in i + 1..assets.len() {
+            let (a, b) = (assets[i], assets[j]);
+            if !readable(a) || !readable(b) {
+                continue;
+            }
+            let identical = a.checksum.is_some() && a.checksum == b.checksum;
+            let similar = a.kind == AssetKind::Image
+                && b.kind == AssetKind::Image
+                && matches!((a.dhash, b.dhash), (Some(x), Some(y)) if (x ^ y).count_ones() <= SIMILAR_BITS);
+            if identical || similar {
+                let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+                parent[rj.max(ri)] = ri.min(rj);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = vec![Vec::new(); assets.len()];
+    for i in 0..assets.len() {
+        let r = root(&mut parent, i);
+        groups[r].push(i);
+    }
+
+    groups
+        .into_iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let first = &assets[members[0]].checksum;
+            let matched = if first.is_some() && members.iter().all(|&m| &assets[m].checksum == first) {
+                Match::Identical
+            } else {
+                Match::Similar
+            };
+            let mut copies = Vec::new();
+            for (n, &m) in members.iter().enumerate() {
+                let original = members[..n]
+                    .iter()
+                    .find(|&&o| assets[m].checksum.is_some() && assets[o].checksum == assets[m].checksum);
+                if let Some(&original) = original {
+                    copies.push((m, original));
+                }
+            }
+            Group { matched, members, copies }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ProcessError;
+
+    fn image(name: &str, checksum: &str, dhash: u64) -> Asset {
+        let mut asset = Asset::stub(Path::new(name), AssetKind::Image);
+        asset.checksum = Some(checksum.to_string());
+        asset.dhash = Some(dhash);
+        asset
+    }
+
+    #[test]
+    fn identical_files_are_copies_of_the_first() {
+        let assets = [image("a.jpg", "x", 0), image("b.jpg", "y", u64::MAX), image("c.jpg", "x", 0)];
+        let groups = find(&assets);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].matched, Match::Identical);
+        assert_eq!(groups[0].members, [0, 2]);
+        assert_eq!(groups[0].copies, [(2, 0)]);
+    }
+
+    #[test]
+    fn similar_pictures_are_never_copies() {
+        let assets = [image("a.jpg", "x", 0b1010), image("b.png", "y", 0b0110)];
+        let groups = find(&assets);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].matched, Match::Similar);
+        assert!(groups[0].copies.is_empty());
+    }
+
+    #[test]
+    fn different_pictures_dont_match() {
+        let assets = [image("a.jpg", "x", 0), image("b.jpg", "y", 0b111_1111)];
+        assert!(find(&assets).is_empty());
+    }
+
+    #[test]
+    fn similar_matches_chain() {
+        // a and c are 12 bits apart, but both are within 6 of b
+        let assets = [image("a.jpg", "x", 0), image("b.jpg", "y", 0b11_1111), image("c.jpg", "z", 0b1111_1111_1111)];
+        let groups = find(&assets);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].members, [0, 1, 2]);
+        assert!(groups[0].copies.is_empty());
+    }
+
+    #[test]
+    fn mixed_group_only_drops_the_identical_copy() {
+        let assets = [image("a.jpg", "x", 0), image("a-small.jpg", "y", 1), image("a-copy.jpg", "x", 0)];
+        let groups = find(&assets);
+        assert_eq!(groups[0].matched, Match::Similar);
+        assert_eq!(groups[0].copies, [(2, 0)]);
+    }
+
+    #[test]
+    fn placeholders_are_left_out() {
+        let mut broken = image("b.jpg", "x", 0);
+        broken.error = Some(ProcessError::Cancelled);
+        assert!(find(&[image("a.jpg", "x", 0), broken]).is_empty());
+    }
+
+    #[test]
+    fn videos_only_match_on_contents() {
+        let mut a = image("a.mov", "x", 0);
+        let mut b = image("b.mov", "y", 0);
+        a.kind = AssetKind::Video;
+        b.kind = AssetKind::Video;
+        assert!(find(&[a, b]).is_empty());
+    }
+}
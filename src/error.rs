@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::cancel::Cancelled;
+
+/// Why no assets could be found in a folder.
+#[derive(Debug, thiserror::Error)]
+pub enum DiscoverError {
+    #[error("'{}' is not a directory", .0.display())]
+    NotADirectory(PathBuf),
+    /// Nothing in the folder was claimed by an extractor.
+    #[error("no supported assets found in '{}'", .0.display())]
+    NoAssets(PathBuf),
+}
+
+/// Why a file couldn't be processed.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ProcessError {
+    /// The file couldn't be opened or read, or its thumbnail written.
+    #[error("cannot read '{}': {message}", path.display())]
+    Io { path: PathBuf, message: String },
+    /// A variant of the format we don't decode, such as a 12-bit JPEG.
+    #[error("unsupported file '{}': {message}", path.display())]
+    Unsupported { path: PathBuf, message: String },
+    /// The file is damaged or isn't what its extension says.
+    #[error("cannot decode '{}': {message}", path.display())]
+    Decode { path: PathBuf, message: String },
+    #[error("cannot probe '{}': ffprobe not found — install with: brew install ffmpeg", path.display())]
+    ProbeMissing { path: PathBuf },
+    #[error("ffprobe failed on '{}': {message}", path.display())]
+    ProbeFailed { path: PathBuf, message: String },
+    #[error("cancelled")]
+    Cancelled,
+}
+
+/// What sort of failure a `ProcessError` is, without the details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Io,
+    Unsupported,
+    Decode,
+    ProbeMissing,
+    ProbeFailed,
+    Cancelled,
+}
+
+impl ErrorKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorKind::Io => "read error",
+            ErrorKind::Unsupported => "unsupported",
+            ErrorKind::Decode => "decode failed",
+            ErrorKind::ProbeMissing => "ffprobe missing",
+            ErrorKind::ProbeFailed => "probe failed",
+            ErrorKind::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl ProcessError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ProcessError::Io { .. } => ErrorKind::Io,
+            ProcessError::Unsupported { .. } => ErrorKind::Unsupported,
+            ProcessError::Decode { .. } => ErrorKind::Decode,
+            ProcessError::ProbeMissing { .. } => ErrorKind::ProbeMissing,
+            ProcessError::ProbeFailed { .. } => ErrorKind::ProbeFailed,
+            ProcessError::Cancelled => ErrorKind::Cancelled,
+        }
+    }
+
+    /// Sort a failure to read or decode the image at `path` into I/O,
+    /// unsupported and decode errors by what's at the bottom of it.
+    pub(crate) fn image(path: &Path, err: anyhow::Error) -> Self {
+        if crate::cancel::is_cancelled(&err) {
+            return ProcessError::Cancelled;
+        }
+        let path = path.to_path_buf();
+        let message = err.root_cause().to_string();
+
+        let unsupported = err.chain().any(|e| {
+            matches!(e.downcast_ref(), Some(image::ImageError::Unsupported(_)))
+                || matches!(e.downcast_ref(), Some(jpeg_decoder::Error::Unsupported(_)))
+        });
+        if unsupported {
+            return ProcessError::Unsupported { path, message };
+        }
+
+        // Truncated and malformed files surface as I/O errors from the
+        // decoders too, but they're decode failures as far as anyone cares.
+        let io = err.chain().any(|e| {
+            let io = match e.downcast_ref() {
+                Some(image::ImageError::IoError(io)) => Some(io),
+                _ => e.downcast_ref::<std::io::Error>(),
+            };
+            io.is_some_and(|io| {
+                !matches!(io.kind(), std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof)
+            })
+        });
+        if io {
+            ProcessError::Io { path, message }
+        } else {
+            ProcessError::Decode { path, message }
+        }
+    }
+}
+
+impl From<Cancelled> for ProcessError {
+    fn from(_: Cancelled) -> Self {
+        ProcessError::Cancelled
+    }
+}
+
+/// Why a PDF or gallery couldn't be rendered.
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("typst not found — install with: brew install typst")]
+    TypstMissing,
+    /// Typst is there but couldn't be started, as when it isn't executable.
+    #[error("cannot run typst")]
+    TypstSpawn {
+        #[source]
+        source: std::io::Error,
+    },
+    /// Typst ran but rejected the document. `diagnostics` is what it
+    /// printed on stderr.
+    #[error("typst compile failed (exit code: {code:?}){}", diagnostics_suffix(.diagnostics))]
+    Typst { code: Option<i32>, diagnostics: String },
+    #[error("cannot write '{}'", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("cancelled")]
+    Cancelled,
+}
+
+impl From<Cancelled> for RenderError {
+    fn from(_: Cancelled) -> Self {
+        RenderError::Cancelled
+    }
+}
+
+fn diagnostics_suffix(diagnostics: &str) -> String {
+    match diagnostics.trim() {
+        "" => String::new(),
+        text => format!("\n{text}"),
+    }
+}
+
+/// Attach the path being written to an I/O error.
+pub(crate) trait WriteContext<T> {
+    fn writing(self, path: &Path) -> Result<T, RenderError>;
+}
+
+impl<T> WriteContext<T> for std::io::Result<T> {
+    fn writing(self, path: &Path) -> Result<T, RenderError> {
+        self.map_err(|source| RenderError::Io { path: path.to_path_buf(), source })
+    }
+}
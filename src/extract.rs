@@ -0,0 +1,312 @@
+//! Metadata extractors: what decides which files are assets, and how their
+//! details and thumbnails are read.
+//!
+//! Every file is offered to the registered extractors in order. The first
+//! to [`claim`](Extractor::claim) it decides whether it's picked up and as
+//! what kind of asset. Then every extractor that
+//! [`applies`](Extractor::applies) to it fills in what it knows, so later
+//! extractors can add to or correct what the built-in ones found:
+//!
+//! ```no_run
+//! use proof::error::ProcessError;
+//! use proof::extract::{Candidate, Extractor, Extractors, Job};
+//! use proof::{Asset, AssetKind};
+//!
+//! /// Colour space from the DAM's `<file>.json` sidecar.
+//! struct Sidecar;
+//!
+//! impl Extractor for Sidecar {
+//!     fn name(&self) -> &'static str {
+//!         "dam-sidecar"
+//!     }
+//!
+//!     fn claim(&self, _: &Candidate) -> Option<AssetKind> {
+//!         None
+//!     }
+//!
+//!     fn applies(&self, file: &Candidate, _: AssetKind) -> bool {
+//!         sidecar(file.path).is_file()
+//!     }
+//!
+//!     fn extract(&self, asset: &mut Asset, _: &Job) -> Result<(), ProcessError> {
+//!         let json = std::fs::read(sidecar(&asset.path)).unwrap_or_default();
+//!         let meta: serde_json::Value = serde_json::from_slice(&json).unwrap_or_default();
+//!         if let Some(cs) = meta["colorSpace"].as_str() {
+//!             asset.color_space = Some(cs.to_string());
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! fn sidecar(path: &std::path::Path) -> std::path::PathBuf {
+//!     let mut name = path.as_os_str().to_owned();
+//!     name.push(".json");
+//!     name.into()
+//! }
+//!
+//! let mut extractors = Extractors::default();
+//! extractors.register(Sidecar);
+//! ```
+
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::ProcessError;
+use crate::scan::{self, Asset, AssetKind, ProcessOptions};
+use crate::sniff::{self, Container, Sniffed};
+
+/// A file being considered, as extractors see it when deciding whether it's
+/// theirs.
+pub struct Candidate<'a> {
+    pub path: &'a Path,
+    /// Lowercase, without the dot. Empty when there isn't one.
+    pub extension: String,
+    sniffed: OnceCell<Sniffed>,
+}
+
+impl<'a> Candidate<'a> {
+    /// The file is only opened if an extractor asks what's in it.
+    pub fn new(path: &'a Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        Self { path, extension, sniffed: OnceCell::new() }
+    }
+
+    /// A file whose first bytes have been read already.
+    pub fn with_sniffed(path: &'a Path, sniffed: Sniffed) -> Self {
+        let candidate = Self::new(path);
+        let _ = candidate.sniffed.set(sniffed);
+        candidate
+    }
+
+    /// What the content is, from its first bytes.
+    pub fn sniffed(&self) -> Sniffed {
+        *self.sniffed.get_or_init(|| sniff::sniff(self.path).unwrap_or(Sniffed::Unknown))
+    }
+}
+
+/// Where and how to process one file.
+pub struct Job<'a> {
+    /// Generated images go here.
+    pub thumb_dir: &'a Path,
+    /// Position of the file in the batch. Generated images are named by it
+    /// so they don't collide.
+    pub index: usize,
+    pub opts: &'a ProcessOptions,
+}
+
+impl Job<'_> {
+    /// Where the contact sheet thumbnail for this file goes.
+    pub fn thumb_path(&self) -> PathBuf {
+        self.thumb_dir.join(format!("{:04}.jpg", self.index))
+    }
+}
+
+/// Reads one sort of file, or one sort of extra information about files.
+pub trait Extractor: Send + Sync {
+    /// Short name, shown when debugging the registry.
+    fn name(&self) -> &'static str;
+
+    /// What this extractor reads `file` as, or `None` to leave it to the
+    /// others.
+    fn claim(&self, file: &Candidate) -> Option<AssetKind>;
+
+    /// Whether to run on a file claimed as `kind`, by this extractor or
+    /// another. Defaults to the files this extractor claims itself.
+    fn applies(&self, file: &Candidate, kind: AssetKind) -> bool {
+        self.claim(file) == Some(kind)
+    }
+
+    /// Fill in what this extractor knows about `asset`, including its
+    /// thumbnail when `job.opts.gen_thumbnails` is set.
+    fn extract(&self, asset: &mut Asset, job: &Job) -> Result<(), ProcessError>;
+}
+
+/// Extractors in the order they're consulted.
+#[derive(Clone)]
+pub struct Extractors(Vec<Arc<dyn Extractor>>);
+
+impl Default for Extractors {
+    /// The built-in image and video extractors.
+    fn default() -> Self {
+        Self(vec![Arc::new(Images), Arc::new(Videos)])
+    }
+}
+
+impl std::fmt::Debug for Extractors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.0.iter().map(|e| e.name())).finish()
+    }
+}
+
+impl Extractors {
+    /// No extractors at all, not even the built-in ones.
+    pub fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Add `extractor` after the ones already registered. To handle a
+    /// format ahead of the built-in extractors, start from `empty`.
+    pub fn register(&mut self, extractor: impl Extractor + 'static) -> &mut Self {
+        self.0.push(Arc::new(extractor));
+        self
+    }
+
+    /// Whether `path` is an asset, and what kind.
+    pub fn kind_of(&self, path: &Path) -> Option<AssetKind> {
+        let file = Candidate::new(path);
+        self.0.iter().find_map(|e| e.claim(&file))
+    }
+
+    /// Run every extractor that applies to `file`, in order, on `asset`.
+    pub fn extract(&self, file: &Candidate, asset: &mut Asset, job: &Job) -> Result<(), ProcessError> {
+        for extractor in &self.0 {
+            if extractor.applies(file, asset.kind) {
+                extractor.extract(asset, job)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Files with a supported extension, as whatever their content turns out
+/// to be. A `.mov` that's really a JPEG is read as an image, and a `.jpg`
+/// that's really a GIF fails as unsupported rather than as a broken JPEG.
+fn builtin_kind(file: &Candidate) -> Option<AssetKind> {
+    let claimed = Container::from_extension(&file.extension)
+        .filter(|c| Container::SUPPORTED.contains(c))?;
+    match file.sniffed() {
+        Sniffed::Known(actual) => Some(actual.kind()),
+        Sniffed::Empty | Sniffed::Unknown => Some(claimed.kind()),
+    }
+}
+
+/// JPEG, PNG, TIFF and WebP stills.
+pub struct Images;
+
+impl Extractor for Images {
+    fn name(&self) -> &'static str {
+        "images"
+    }
+
+    fn claim(&self, file: &Candidate) -> Option<AssetKind> {
+        builtin_kind(file).filter(|&k| k == AssetKind::Image)
+    }
+
+    fn extract(&self, asset: &mut Asset, job: &Job) -> Result<(), ProcessError> {
+        scan::process_image(asset, job)
+    }
+}
+
+/// MP4, MOV and MXF clips, through ffprobe and ffmpeg.
+pub struct Videos;
+
+impl Extractor for Videos {
+    fn name(&self) -> &'static str {
+        "videos"
+    }
+
+    fn claim(&self, file: &Candidate) -> Option<AssetKind> {
+        builtin_kind(file).filter(|&k| k == AssetKind::Video)
+    }
+
+    fn extract(&self, asset: &mut Asset, job: &Job) -> Result<(), ProcessError> {
+        scan::process_video(asset, job)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static RAN: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Claims files with `extension` as `kind`, and runs on everything when
+    /// `everywhere` is set. Logs its name in `RAN` so the order it ran in
+    /// shows.
+    struct Stub {
+        name: &'static str,
+        claims: Option<(&'static str, AssetKind)>,
+        everywhere: bool,
+    }
+
+    impl Extractor for Stub {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn claim(&self, file: &Candidate) -> Option<AssetKind> {
+            self.claims.filter(|(ext, _)| file.extension == *ext).map(|(_, kind)| kind)
+        }
+
+        fn applies(&self, file: &Candidate, kind: AssetKind) -> bool {
+            self.everywhere || self.claim(file) == Some(kind)
+        }
+
+        fn extract(&self, _: &mut Asset, _: &Job) -> Result<(), ProcessError> {
+            RAN.with_borrow_mut(|ran| ran.push(self.name));
+            Ok(())
+        }
+    }
+
+    fn claiming(name: &'static str, extension: &'static str, kind: AssetKind) -> Stub {
+        Stub { name, claims: Some((extension, kind)), everywhere: false }
+    }
+
+    /// Names of the extractors that ran on `path`, in order.
+    fn ran(extractors: &Extractors, path: &str, kind: AssetKind) -> Vec<&'static str> {
+        let path = Path::new(path);
+        let opts = ProcessOptions::default();
+        let job = Job { thumb_dir: Path::new("/nonexistent"), index: 0, opts: &opts };
+        let mut asset = Asset::stub(path, kind);
+        extractors.extract(&Candidate::new(path), &mut asset, &job).unwrap();
+        RAN.take()
+    }
+
+    #[test]
+    fn first_claim_wins() {
+        let mut extractors = Extractors::empty();
+        extractors
+            .register(claiming("first", "raw", AssetKind::Video))
+            .register(claiming("second", "raw", AssetKind::Image));
+        assert_eq!(extractors.kind_of(Path::new("clip.raw")), Some(AssetKind::Video));
+        assert_eq!(extractors.kind_of(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn every_extractor_that_applies_runs_in_order() {
+        let mut extractors = Extractors::empty();
+        extractors
+            .register(claiming("raw-stills", "raw", AssetKind::Image))
+            .register(claiming("raw-clips", "raw", AssetKind::Video))
+            .register(Stub { name: "everywhere", claims: None, everywhere: true });
+        assert_eq!(ran(&extractors, "still.raw", AssetKind::Image), ["raw-stills", "everywhere"]);
+        assert_eq!(ran(&extractors, "clip.raw", AssetKind::Video), ["raw-clips", "everywhere"]);
+        assert_eq!(ran(&extractors, "notes.txt", AssetKind::Image), ["everywhere"]);
+    }
+
+    #[test]
+    fn registering_on_empty_overrides_the_builtins() {
+        // A missing file sniffs as unknown, so its extension decides.
+        let path = Path::new("/nonexistent/photo.jpg");
+        assert_eq!(Extractors::default().kind_of(path), Some(AssetKind::Image));
+
+        let mut after = Extractors::default();
+        after.register(claiming("jpeg-clips", "jpg", AssetKind::Video));
+        assert_eq!(after.kind_of(path), Some(AssetKind::Image));
+
+        let mut instead = Extractors::empty();
+        instead
+            .register(claiming("jpeg-clips", "jpg", AssetKind::Video))
+            .register(Images)
+            .register(Videos);
+        assert_eq!(instead.kind_of(path), Some(AssetKind::Video));
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, RgbImage};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+use crate::cli::Graphics;
+
+/// How images are drawn in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    Kitty,
+    Iterm,
+    Sixel,
+    /// Unicode half blocks coloured per cell. Works everywhere.
+    HalfBlocks,
+}
+
+impl Protocol {
+    /// Pick a protocol from the flag, or from the environment for `auto`.
+    /// Inside tmux or screen, graphics escapes don't reach the outer
+    /// terminal, so auto-detection stays on half blocks there.
+    pub fn select(flag: Graphics) -> Self {
+        match flag {
+            Graphics::Kitty => return Protocol::Kitty,
+            Graphics::Iterm => return Protocol::Iterm,
+            Graphics::Sixel => return Protocol::Sixel,
+            Graphics::Blocks => return Protocol::HalfBlocks,
+            Graphics::Auto => {}
+        }
+
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+
+        if std::env::var_os("TMUX").is_some() || term.starts_with("screen") {
+            Protocol::HalfBlocks
+        } else if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || program == "ghostty"
+        {
+            Protocol::Kitty
+        } else if program == "iTerm.app" || program == "WezTerm" {
+            Protocol::Iterm
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            Protocol::Sixel
+        } else {
+            Protocol::HalfBlocks
+        }
+    }
+}
+
+/// Size of a terminal cell in pixels, falling back to a typical 8x16.
+fn cell_size() -> (u32, u32) {
+    match crossterm::terminal::window_size() {
+        Ok(ws) if ws.width > 0 && ws.height > 0 && ws.columns > 0 && ws.rows > 0 => (
+            (ws.width / ws.columns).max(1) as u32,
+            (ws.height / ws.rows).max(1) as u32,
+        ),
+        _ => (8, 16),
+    }
+}
+
+/// Largest cell box with the image's aspect ratio that fits in `area`.
+fn fit(img: &DynamicImage, area: Rect, cell: (u32, u32)) -> (u16, u16) {
+    let (w, h) = img.dimensions();
+    let (cw, ch) = cell;
+    let max_w = area.width as u32 * cw;
+    let max_h = area.height as u32 * ch;
+    let scale = (max_w as f64 / w as f64).min(max_h as f64 / h as f64);
+    let cols = ((w as f64 * scale) / cw as f64).round().clamp(1.0, area.width as f64);
+    let rows = ((h as f64 * scale) / ch as f64).round().clamp(1.0, area.height as f64);
+    (cols as u16, rows as u16)
+}
+
+/// Draw `img` into `area` with a graphics protocol, replacing whatever
+/// image was there before. Not used for half blocks, which are ordinary
+/// ratatui text.
+pub fn draw(out: &mut impl Write, protocol: Protocol, img: &DynamicImage, area: Rect) -> std::io::Result<()> {
+    clear(out, protocol, area)?;
+    let cell = cell_size();
+    let (cols, rows) = fit(img, area, cell);
+    crossterm::queue!(out, crossterm::cursor::MoveTo(area.x, area.y))?;
+
+    match protocol {
+        Protocol::Kitty => {
+            let png = encode_png(img)?;
+            let data = base64::engine::general_purpose::STANDARD.encode(png);
+            let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(i + 1 < chunks.len());
+                if i == 0 {
+                    write!(out, "\x1b_Ga=T,f=100,q=2,C=1,c={cols},r={rows},m={more};")?;
+                } else {
+                    write!(out, "\x1b_Gm={more};")?;
+                }
+                out.write_all(chunk)?;
+                write!(out, "\x1b\\")?;
+            }
+        }
+        Protocol::Iterm => {
+            let png = encode_png(img)?;
+            let data = base64::engine::general_purpose::STANDARD.encode(&png);
+            write!(
+                out,
+                "\x1b]1337;File=inline=1;size={};width={cols};height={rows};preserveAspectRatio=1:{data}\x07",
+                png.len()
+            )?;
+        }
+        Protocol::Sixel => {
+            let rgb = img
+                .resize(cols as u32 * cell.0, rows as u32 * cell.1, FilterType::Triangle)
+                .to_rgb8();
+            out.write_all(sixel(&rgb).as_bytes())?;
+        }
+        Protocol::HalfBlocks => {}
+    }
+
+    out.flush()
+}
+
+/// Remove a previously drawn image from `area`.
+pub fn clear(out: &mut impl Write, protocol: Protocol, area: Rect) -> std::io::Result<()> {
+    match protocol {
+        Protocol::Kitty => write!(out, "\x1b_Ga=d,d=a,q=2\x1b\\")?,
+        // Sixel and iTerm images live in the cells themselves, so blanking
+        // the cells erases them.
+        Protocol::Iterm | Protocol::Sixel => {
+            let blank = " ".repeat(area.width as usize);
+            for y in area.top()..area.bottom() {
+                crossterm::queue!(out, crossterm::cursor::MoveTo(area.x, y))?;
+                out.write_all(blank.as_bytes())?;
+            }
+        }
+        Protocol::HalfBlocks => {}
+    }
+    out.flush()
+}
+
+/// Render `img` as rows of `▀` with the upper pixel as foreground and the
+/// lower as background, two pixels per cell vertically.
+pub fn half_blocks(img: &DynamicImage, area: Rect) -> Vec<Line<'static>> {
+    let (cols, rows) = fit(img, area, (1, 2));
+    let rgb = img
+        .resize_exact(cols as u32, rows as u32 * 2, FilterType::Triangle)
+        .to_rgb8();
+
+    (0..rows as u32)
+        .map(|row| {
+            let spans: Vec<Span> = (0..cols as u32)
+                .map(|x| {
+                    let top = rgb.get_pixel(x, row * 2);
+                    let bottom = rgb.get_pixel(x, row * 2 + 1);
+                    Span::styled(
+                        "\u{2580}",
+                        Style::default()
+                            .fg(Color::Rgb(top[0], top[1], top[2]))
+                            .bg(Color::Rgb(bottom[0], bottom[1], bottom[2])),
+                    )
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn encode_png(img: &DynamicImage) -> std::io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(img.to_rgb8())
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(std::io::Error::other)?;
+    Ok(png)
+}
+
+/// Encode as sixel with a fixed 6x6x6 colour cube palette.
+fn sixel(img: &RgbImage) -> String {
+    let (w, h) = img.dimensions();
+    let level = |v: u8| (v as usize * 5 + 127) / 255;
+    let index = |p: &image::Rgb<u8>| level(p[0]) * 36 + level(p[1]) * 6 + level(p[2]);
+
+    let mut out = format!("\x1bPq\"1;1;{w};{h}");
+    for i in 0..216 {
+        let _ = write!(out, "#{};2;{};{};{}", i, i / 36 * 20, i / 6 % 6 * 20, i % 6 * 20);
+    }
+
+    for band in (0..h).step_by(6) {
+        // Bitmask of the six rows in this band, per colour and column.
+        let mut colours: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+        for x in 0..w {
+            for dy in 0..6.min(h - band) {
+                let c = index(img.get_pixel(x, band + dy));
+                colours.entry(c).or_insert_with(|| vec![0; w as usize])[x as usize] |= 1 << dy;
+            }
+        }
+
+        for (n, (colour, bits)) in colours.iter().enumerate() {
+            if n > 0 {
+                out.push('$');
+            }
+            let _ = write!(out, "#{colour}");
+            let mut run = bits.iter().peekable();
+            while let Some(&b) = run.next() {
+                let mut count = 1;
+                while run.peek() == Some(&&b) {
+                    run.next();
+                    count += 1;
+                }
+                let ch = (63 + b) as char;
+                if count > 3 {
+                    let _ = write!(out, "!{count}{ch}");
+                } else {
+                    out.extend(std::iter::repeat_n(ch, count));
+                }
+            }
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
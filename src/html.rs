@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::cancel::{Cancel, Cancelled};
+use crate::error::{RenderError, WriteContext};
+use crate::pdf::{self, PdfConfig};
+use crate::scan::{Asset, AssetKind};
+
+// Longest edge of the images opened from the grid, in pixels.
+const PREVIEW_SIZE: u32 = 1600;
+// Video previews are cut to this width and length.
+const CLIP_WIDTH: u32 = 960;
+const CLIP_SECONDS: u32 = 30;
+
+/// Write a self-contained gallery into `dir`: `index.html` plus the
+/// thumbnails, previews and clips it links to, so the folder can be opened
+/// from disk or copied to any static host.
+///
+/// Rendering into the same `dir` again, as watch mode does, only makes
+/// previews for files that changed, and removes the thumbnails and previews
+/// of files that are no longer listed.
+pub fn render(assets: &[Asset], config: &PdfConfig, dir: &Path, cancel: &Cancel) -> Result<(), RenderError> {
+    cancel.check()?;
+    std::fs::create_dir_all(dir.join("previews")).writing(dir)?;
+    let mut thumbs = pdf::copy_thumbs(assets, dir)?;
+    thumbs.extend(pdf::write_previous_thumbs(config, dir)?);
+    let hero = pdf::prepare_hero(assets, config, dir);
+
+    let previews: Vec<Option<String>> = assets
+        .par_iter()
+        .map(|asset| preview(asset, config, dir, cancel))
+        .collect::<Result<_, _>>()?;
+
+    let made: Vec<PathBuf> = previews.iter().flatten().map(|name| dir.join(name)).collect();
+    remove_others(&dir.join("thumbs"), &thumbs);
+    remove_others(&dir.join("previews"), &made);
+
+    let mut data = pdf::build_data(assets, config, hero);
+    for (entry, preview) in data.assets.iter_mut().zip(previews) {
+        entry.preview = preview;
+    }
+
+    // Keep a stray `</script>` in a filename from ending the data block
+    let json = serde_json::to_string(&data)
+        .expect("template data always serializes")
+        .replace("</", "<\\/");
+    let html = include_str!("../templates/gallery.html").replace("/*DATA*/null", &json);
+    let index = dir.join("index.html");
+    std::fs::write(&index, html).writing(&index)
+}
+
+/// Make the large version of one asset and return its path relative to the
+/// gallery. Images get a bigger JPEG, videos a short H.264 clip. Nothing is
+/// made for unreadable files, or when conversion fails, and the gallery
+/// shows the thumbnail instead. A preview already made from the same file
+/// is kept as it is.
+fn preview(asset: &Asset, config: &PdfConfig, dir: &Path, cancel: &Cancel) -> Result<Option<String>, Cancelled> {
+    cancel.check()?;
+    if asset.error.is_some() {
+        return Ok(None);
+    }
+
+    let key = preview_key(asset, config);
+    let extension = match asset.kind {
+        AssetKind::Image => "jpg",
+        AssetKind::Video => "mp4",
+    };
+    let name = format!("previews/{key}.{extension}");
+    if dir.join(&name).is_file() {
+        return Ok(Some(name));
+    }
+    // Made under another name and renamed once complete, so a cancelled
+    // preview is never taken for a finished one
+    let out = dir.join(format!("previews/{key}.part.{extension}"));
+    let finish = |made: bool| {
+        if made && std::fs::rename(&out, dir.join(&name)).is_ok() {
+            return Some(name.clone());
+        }
+        let _ = std::fs::remove_file(&out);
+        None
+    };
+
+    match asset.kind {
+        AssetKind::Image => {
+            let written = crate::scan::preview(&asset.path, PREVIEW_SIZE, config.auto_orient)
+                .and_then(|img| crate::thumb::save_jpeg(&img, &out, 85));
+            Ok(finish(written.is_ok()))
+        }
+        AssetKind::Video => {
+            // Even height, as yuv420p requires
+            let scale = format!("scale='min({CLIP_WIDTH},iw)':-2");
+            let status = cancel.status(
+                std::process::Command::new("ffmpeg")
+                    .args(["-y", "-i"])
+                    .arg(&asset.path)
+                    .args(["-t", &CLIP_SECONDS.to_string(), "-vf", &scale])
+                    .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "26", "-pix_fmt", "yuv420p"])
+                    .args(["-c:a", "aac", "-b:a", "128k", "-movflags", "+faststart"])
+                    .arg(&out)
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null()),
+            );
+            match status {
+                Ok(s) => Ok(finish(s.success() && out.is_file())),
+                Err(e) if crate::cancel::is_cancelled(&e) => {
+                    finish(false);
+                    Err(Cancelled)
+                }
+                Err(_) => Ok(finish(false)),
+            }
+        }
+    }
+}
+
+/// What a preview is named by: the file's checksum, or its path and
+/// modification time without one, and the settings it was made with. It
+/// changes whenever the file does, and not when other files come and go.
+fn preview_key(asset: &Asset, config: &PdfConfig) -> String {
+    let mut hasher = Sha256::new();
+    match asset.checksum {
+        Some(ref sum) => hasher.update(sum.as_bytes()),
+        None => {
+            hasher.update(asset.path.to_string_lossy().as_bytes());
+            let modified = std::fs::metadata(&asset.path).and_then(|m| m.modified()).ok();
+            hasher.update(format!("{modified:?}").as_bytes());
+        }
+    }
+    if asset.kind == AssetKind::Image && config.auto_orient {
+        hasher.update(b"auto-orient");
+    }
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// Delete the files in `dir` other than `keep`, left from earlier renders.
+/// Failing to is harmless, so errors are ignored.
+fn remove_others(dir: &Path, keep: &[PathBuf]) {
+    let keep: HashSet<&Path> = keep.iter().map(PathBuf::as_path).collect();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() && !keep.contains(path.as_path()) {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Scan a folder of finals and render a branded delivery proof from it.
+//!
+//! The `proof` command line tool is a thin layer over this crate. The
+//! pipeline is the same for any caller: [`discover`] the assets, [`process`]
+//! them into [`Asset`]s with thumbnails and metadata, then [`render`] a PDF.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use std::path::Path;
+//!
+//! let found = proof::discover(Path::new("finals"))?;
+//! let thumbs = tempfile::tempdir()?;
+//! let mut opts = proof::ProcessOptions::default();
+//! opts.auto_orient = true;
+//! opts.thumb_quality = 85;
+//! let (assets, _skipped) = proof::process(&found, thumbs.path(), &opts, |_| {});
+//!
+//! let mut config = proof::PdfConfig::new("Armani", "2025-03-01");
+//! config.auto_orient = true;
+//! proof::render(&assets, &config, Path::new("armani.pdf"), &opts.cancel)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Around that, [`html`] and [`manifest`] write the gallery and manifest
+//! exports, [`changes`] compares against a previous delivery,
+//! [`deliveries`] keeps the revision log, [`find_duplicates`] groups
+//! repeated files and [`write_package`] zips everything up.
+
+pub mod cancel;
+pub mod changes;
+mod color;
+pub mod config;
+pub mod deliveries;
+mod dupes;
+pub mod error;
+pub mod extract;
+pub mod html;
+pub mod manifest;
+mod naming;
+mod package;
+pub mod pdf;
+mod qc;
+pub mod scan;
+mod sniff;
+/// Thumbnail decoding, public only for the benchmarks.
+#[doc(hidden)]
+pub mod thumb;
+
+pub use dupes::{find as find_duplicates, Group as DuplicateGroup, Match as DuplicateMatch};
+pub use error::{DiscoverError, ErrorKind, ProcessError, RenderError};
+pub use extract::{Extractor, Extractors};
+pub use naming::{output_for, Fields as OutputFields, Template as OutputTemplate};
+pub use package::{write as write_package, Contents as PackageContents, Packaged};
+pub use pdf::{render, PdfConfig};
+pub use qc::{Issue as QcIssue, IssueKind as QcIssueKind};
+pub use scan::{discover, process_all as process, Asset, AssetKind, ProcessOptions, Progress};
+pub use sniff::{Container, Sniffed};
@@ -1,33 +1,169 @@
-use anyhow::Result;
+use std::path::Path;
+
+use anyhow::{Context, Result};
 use clap::Parser;
 
 mod cli;
-mod pdf;
-mod scan;
+mod graphics;
+mod progress;
 mod tui;
+mod watch;
 
-fn main() {
-    if let Err(err) = run() {
-        eprintln!("error: {:#}", err);
-        std::process::exit(1);
+use proof::{cancel, changes, config, deliveries, html, pdf, scan};
+use proof::{find_duplicates, output_for, write_package, PackageContents};
+use progress::{Event, Reporter};
+
+/// Process exit codes. Scripts rely on these, so don't renumber them.
+#[derive(Debug, Clone, Copy)]
+enum Exit {
+    /// Arguments that don't go together, as clap exits with for the ones it
+    /// checks itself.
+    Usage = 2,
+    /// The input has no assets, or none could be processed.
+    NoAssets = 3,
+    /// Typst is missing or the PDF couldn't be written.
+    Render = 4,
+    /// The PDF was delivered, but the gallery, manifest or package that
+    /// goes with it couldn't be written.
+    Extras = 5,
+    /// Interrupted, as with SIGINT.
+    Cancelled = 130,
+}
+
+impl std::fmt::Display for Exit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Exit::Usage => write!(f, "invalid arguments"),
+            Exit::NoAssets => write!(f, "no assets to deliver"),
+            Exit::Render => write!(f, "cannot render PDF"),
+            Exit::Extras => write!(f, "PDF delivered, but not everything that goes with it"),
+            Exit::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+fn usage(message: &str) -> anyhow::Error {
+    anyhow::anyhow!("{message}").context(Exit::Usage)
+}
+
+fn exit_code(err: &anyhow::Error) -> i32 {
+    if cancel::is_cancelled(err) {
+        return Exit::Cancelled as i32;
+    }
+    err.downcast_ref::<Exit>().map_or(1, |&code| code as i32)
+}
+
+/// Whether `path`, which may not exist yet, is in `dir` or is `dir`.
+fn is_within(path: &Path, dir: &Path) -> bool {
+    let Ok(dir) = std::fs::canonicalize(dir) else {
+        return false;
+    };
+    // Resolve the part of `path` that exists and add the rest back
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut existing = absolute.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return false,
+        }
     }
+    let Ok(mut resolved) = std::fs::canonicalize(existing) else {
+        return false;
+    };
+    resolved.extend(missing.iter().rev());
+    resolved.starts_with(dir)
 }
 
-fn run() -> Result<()> {
+fn main() {
     let cli = cli::Cli::parse();
+    let reporter = Reporter { json: cli.progress == cli::ProgressFormat::Json };
+
+    if let Err(err) = run(cli, reporter) {
+        let code = exit_code(&err);
+        if reporter.json {
+            reporter.emit(Event::Error { code: Some(code), message: format!("{:#}", err) });
+        } else {
+            eprintln!("error: {:#}", err);
+        }
+        std::process::exit(code);
+    }
+}
+
+fn run(cli: cli::Cli, reporter: Reporter) -> Result<()> {
+    if let Some(jobs) = cli.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs as usize)
+            .build_global()?;
+    }
+
+    if !cli.input().is_dir() {
+        return Err(usage(&format!("'{}' is not a directory", cli.input().display())));
+    }
+    if cli.manifest_only && cli.html.is_some() {
+        return Err(usage("--manifest-only can't be used with --html"));
+    }
+    if cli.is_watch() && cli.manifest_only {
+        return Err(usage("--manifest-only can't be used with watch"));
+    }
+    if cli.is_watch() && cli.package.is_some() {
+        return Err(usage("--package can't be used with watch; package the final version with a normal run"));
+    }
+    if cli.html.as_deref().is_some_and(|dir| is_within(dir, cli.input())) {
+        return Err(usage("--html can't be inside the input folder, or its previews would be proofed too"));
+    }
 
-    // TUI mode is default unless --no-tui or --manifest-only
-    if !cli.no_tui && !cli.manifest_only {
+    // TUI mode is default unless --no-tui, --manifest-only or JSON progress
+    if !cli.no_tui && !cli.manifest_only && !reporter.json {
         return tui::run(cli);
     }
 
-    let date = cli
-        .date
-        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
-    let client = cli.client.unwrap_or_else(|| String::from("Delivery"));
+    // Stop at the next file or step on Ctrl-C, and straight away on a
+    // second one
+    let cancel = cancel::Cancel::default();
+    let on_interrupt = cancel.clone();
+    ctrlc::set_handler(move || {
+        if on_interrupt.is_cancelled() {
+            std::process::exit(Exit::Cancelled as i32);
+        }
+        on_interrupt.cancel();
+    })?;
+
+    let input = cli.input().to_path_buf();
+    reporter.emit(Event::Start {
+        schema: progress::SCHEMA,
+        version: env!("CARGO_PKG_VERSION"),
+        input: progress::display(&input),
+    });
+
+    if let Some(cli::Command::Watch { debounce, .. }) = cli.command {
+        return watch(&cli, debounce, &cancel, reporter);
+    }
 
-    eprintln!("Scanning {}...", cli.input.display());
-    let found = scan::discover(&cli.input)?;
+    let manifest = cli.manifest();
+    let previous = cli.previous().map(changes::Previous::load).transpose()?;
+    let project = config::Config::load(&input)?;
+    let mut opts = cli.process_options(&project);
+    opts.cancel = cancel;
+    let details = cli.details(&project);
+
+    reporter.say(format_args!("Scanning {}...", input.display()));
+    // The input was checked to be a folder, so this only fails when it has
+    // no assets
+    let mut found = scan::discover(&input).context(Exit::NoAssets)?;
+    let before = found.len();
+    found.retain(|(path, _)| !project.is_excluded(&input, path));
+    if found.len() < before {
+        reporter.say(format_args!("Skipping {} files excluded in {}", before - found.len(), config::FILENAME));
+    }
+    project.apply_order(&input, &mut found);
+    let hero = found
+        .iter()
+        .find(|(path, _)| project.is_hero(&input, path))
+        .map(|(path, _)| path.clone());
 
     let image_count = found
         .iter()
@@ -37,60 +173,255 @@ fn run() -> Result<()> {
         .iter()
         .filter(|(_, k)| *k == scan::AssetKind::Video)
         .count();
-    eprintln!(
+    reporter.say(format_args!(
         "Found {} assets ({} images, {} videos)",
         found.len(),
         image_count,
         video_count
-    );
+    ));
+    reporter.found(&found);
 
-    let gen_thumbnails = !cli.manifest_only;
     let thumb_dir = tempfile::tempdir()?;
-    let (assets, errors) = scan::process_all(&found, thumb_dir.path(), gen_thumbnails, cli.auto_orient);
+    reporter.say(format_args!("Processing {} assets...", found.len()));
+    let (assets, errors) = scan::process_all(&found, thumb_dir.path(), &opts, |p| {
+        reporter.progress(&found, &p)
+    });
 
     if !errors.is_empty() {
-        eprintln!("\n{} files skipped:", errors.len());
+        reporter.say(format_args!("\n{} files skipped:", errors.len()));
         for err in &errors {
-            eprintln!("  - {}", err);
+            reporter.say(format_args!("  - {}", err));
+        }
+    }
+
+    let warnings: Vec<String> = assets
+        .iter()
+        .flat_map(|a| a.warnings.iter().map(move |w| format!("{}: {w}", a.filename)))
+        .collect();
+    if !warnings.is_empty() {
+        reporter.say(format_args!("\n{} warnings:", warnings.len()));
+        for warning in &warnings {
+            reporter.say(format_args!("  - {}", warning));
+        }
+    }
+
+    let report = |problems: bool| -> Vec<String> {
+        assets
+            .iter()
+            .flat_map(|a| {
+                a.issues()
+                    .iter()
+                    .filter(move |i| i.kind.is_problem() == problems)
+                    .map(move |i| format!("{}: {}: {}", a.filename, i.kind.label(), i.message))
+            })
+            .collect()
+    };
+    let (issues, unchecked) = (report(true), report(false));
+    if cli.deep_check {
+        let checked = assets.iter().filter(|a| !a.is_unchecked()).count();
+        match issues.len() {
+            0 => reporter.say(format_args!("\nDeep check: {checked} files decoded cleanly")),
+            n => {
+                reporter.say(format_args!("\n{n} integrity issues:"));
+                for issue in &issues {
+                    reporter.say(format_args!("  - {}", issue));
+                }
+            }
+        }
+        if !unchecked.is_empty() {
+            reporter.say(format_args!("\n{} files not checked:", unchecked.len()));
+            for file in &unchecked {
+                reporter.say(format_args!("  - {}", file));
+            }
+        }
+    }
+
+    let duplicates = find_duplicates(&assets);
+    if !duplicates.is_empty() {
+        reporter.say(format_args!("\n{} sets of duplicates:", duplicates.len()));
+        for group in &duplicates {
+            let files: Vec<&str> = group.members.iter().map(|&m| assets[m].filename.as_str()).collect();
+            reporter.say(format_args!("  - {}: {}", group.matched.label(), files.join(", ")));
+        }
+    }
+
+    let changes = previous.map(|previous| changes::compare(&previous, &assets, &input));
+    if let Some(ref changes) = changes {
+        reporter.say(format_args!("\nChanges since {}: {}", changes.since, changes.describe()));
+        for &index in &changes.added {
+            reporter.say(format_args!("  + {}", assets[index].filename));
+        }
+        for file in &changes.removed {
+            reporter.say(format_args!("  - {}", file.name));
+        }
+        for (index, _) in &changes.replaced {
+            reporter.say(format_args!("  ~ {}", assets[*index].filename));
+        }
+        for (index, file) in &changes.renamed {
+            reporter.say(format_args!("  > {} → {}", file.name, assets[*index].filename));
         }
     }
 
     if assets.is_empty() {
-        anyhow::bail!("No assets could be processed");
+        return Err(anyhow::anyhow!("No assets could be processed").context(Exit::NoAssets));
     }
+    let summary = progress::Summary::new(&assets, errors.len());
+    let manifest_out = manifest.out.as_deref().map(progress::display);
 
     if cli.manifest_only {
-        println!("Filename\tType\tResolution\tFormat\tSize\tColor Space");
-        for a in &assets {
-            println!(
-                "{}\t{}\t{}\t{}\t{}\t{}",
-                a.filename,
-                a.kind,
-                a.resolution(),
-                a.format,
-                a.human_size(),
-                a.color_space.as_deref().unwrap_or("—")
-            );
-        }
+        manifest.write(&assets, &input)?;
+        reporter.emit(Event::Done {
+            output: None,
+            html: None,
+            package: None,
+            manifest: manifest_out,
+            revision: None,
+            summary,
+        });
         return Ok(());
     }
 
-    let output = cli.output.unwrap_or_else(|| {
-        let slug = client.to_lowercase().replace(' ', "-");
-        std::path::PathBuf::from(format!("{}-delivery-{}.pdf", slug, date))
-    });
+    let revision = deliveries::Log::load(&input)?.next_revision();
+    let fields = details.fields(revision, assets.len(), &input);
+    let output = output_for(cli.output.as_deref(), &cli.template(), &fields);
 
-    let config = pdf::PdfConfig {
-        client: client.clone(),
-        title: cli.title,
-        date,
-        columns: cli.columns,
-        auto_orient: cli.auto_orient,
-    };
+    let mut config = pdf::PdfConfig::new(details.client, details.date);
+    config.title = details.title;
+    config.columns = cli.columns;
+    config.auto_orient = cli.auto_orient;
+    config.hero = hero;
+    config.skip_duplicates = cli.skip_duplicates;
+    config.changes = changes;
+    config.revision = Some(revision);
 
-    eprintln!("Generating PDF...");
-    pdf::render(&assets, &config, &output)?;
-    eprintln!("Done: {} ({} assets)", output.display(), assets.len());
+    reporter.say("Generating PDF...");
+    reporter.emit(Event::Rendering);
+    pdf::render(&assets, &config, &output, &opts.cancel).context(Exit::Render)?;
+    // The PDF is out, so this revision is taken even if what follows fails.
+    let delivery = deliveries::Delivery::new(revision, &config.client, &config.date, &assets, &input, &output);
+    if let Some(message) = deliveries::record_or_warn(&input, delivery) {
+        reporter.say(format_args!("warning: {message}"));
+        reporter.emit(Event::Warning { message });
+    }
+    if let Some(ref dir) = cli.html {
+        reporter.say("Generating HTML gallery...");
+        html::render(&assets, &config, dir, &opts.cancel).context(Exit::Extras)?;
+    }
+    if manifest.out.is_some() {
+        manifest.write(&assets, &input).context(Exit::Extras)?;
+    }
+    let packaged = match cli.package {
+        Some(ref zip) => {
+            reporter.say("Packaging...");
+            let contents = PackageContents {
+                assets: &assets,
+                root: &input,
+                proof: &output,
+                readme: cli.package_readme.as_deref(),
+            };
+            Some(write_package(zip, &contents, &opts.cancel).context(Exit::Extras)?)
+        }
+        None => None,
+    };
+    reporter.say(format_args!(
+        "Done: {} (delivery {revision}, {} assets)",
+        output.display(),
+        assets.len()
+    ));
+    if let Some(ref dir) = cli.html {
+        reporter.say(format_args!("Gallery: {}", dir.join("index.html").display()));
+    }
+    if let (Some(zip), Some(packaged)) = (&cli.package, &packaged) {
+        reporter.say(format_args!(
+            "Package: {} ({} files, {}, verified)",
+            zip.display(),
+            packaged.files,
+            humansize::format_size(packaged.bytes, humansize::BINARY)
+        ));
+    }
+    reporter.emit(Event::Done {
+        output: Some(progress::display(&output)),
+        html: cli.html.as_deref().map(progress::display),
+        package: cli.package.as_deref().map(progress::display),
+        manifest: manifest_out,
+        revision: Some(revision),
+        summary,
+    });
 
     Ok(())
 }
+
+/// `proof watch` without the TUI.
+fn watch(cli: &cli::Cli, debounce: std::time::Duration, cancel: &cancel::Cancel, reporter: Reporter) -> Result<()> {
+    let thumb_dir = tempfile::tempdir()?;
+    let found = std::sync::Mutex::new(Vec::new());
+
+    reporter.say(format_args!("Watching {}... (Ctrl-C to stop)", cli.input().display()));
+    watch::run(cli, debounce, thumb_dir.path(), cancel, |event| match event {
+        watch::Event::Scanning => {
+            reporter.say("\nScanning...");
+            reporter.emit(Event::Scanning);
+        }
+        watch::Event::Found(files) => {
+            reporter.say(format_args!("Found {} assets", files.len()));
+            reporter.found(&files);
+            *found.lock().unwrap() = files;
+        }
+        watch::Event::Progress(progress) => {
+            let found = found.lock().unwrap();
+            match progress {
+                scan::Progress::Processing { index } => {
+                    if let Some((path, _)) = found.get(index) {
+                        reporter.say(format_args!("  processing {}", path.display()));
+                    }
+                }
+                scan::Progress::Failed { ref error, .. } => reporter.say(format_args!("  - {error}")),
+                scan::Progress::Processed { ref asset, .. } => {
+                    for warning in &asset.warnings {
+                        reporter.say(format_args!("  warning: {}: {warning}", asset.filename));
+                    }
+                    for issue in asset.issues() {
+                        reporter.say(format_args!(
+                            "  qc: {}: {}: {}",
+                            asset.filename,
+                            issue.kind.label(),
+                            issue.message
+                        ));
+                    }
+                }
+            }
+            reporter.progress(&found, &progress);
+        }
+        watch::Event::Rendering => {
+            reporter.say("Generating PDF...");
+            reporter.emit(Event::Rendering);
+        }
+        watch::Event::Rendered { output, revision, summary } => {
+            reporter.say(format_args!(
+                "Done: {} (delivery {revision}, {} assets)",
+                output.display(),
+                summary.total_files
+            ));
+            reporter.emit(Event::Done {
+                output: Some(progress::display(&output)),
+                html: cli.html.as_deref().map(progress::display),
+                package: cli.package.as_deref().map(progress::display),
+                manifest: cli.manifest_out.as_deref().map(progress::display),
+                revision: Some(revision),
+                summary,
+            });
+        }
+        watch::Event::Unchanged => {
+            reporter.say("No changes to the proof");
+            reporter.emit(Event::Unchanged);
+        }
+        watch::Event::Warning(message) => {
+            reporter.say(format_args!("warning: {message}"));
+            reporter.emit(Event::Warning { message });
+        }
+        watch::Event::Error(message) => {
+            reporter.say(format_args!("error: {message}"));
+            reporter.emit(Event::Error { code: None, message });
+        }
+    })
+}
@@ -0,0 +1,293 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+
+use crate::config;
+use crate::scan::Asset;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    Tsv,
+    Csv,
+    Json,
+    Xlsx,
+    /// Markdown table
+    Md,
+}
+
+impl Format {
+    /// Guess from a file extension, as in `--manifest-out manifest.csv`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::from_str(&ext, true).ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Column {
+    Filename,
+    /// Path relative to the input directory
+    Path,
+    Kind,
+    /// `WIDTHxHEIGHT`
+    Resolution,
+    Width,
+    Height,
+    Format,
+    /// Human-readable size
+    Size,
+    /// Size in bytes
+    Bytes,
+    ColorSpace,
+    /// Duration in seconds
+    Duration,
+    Codec,
+    /// SHA-256 of the file, in hex
+    Checksum,
+    /// Small JPEG preview as a `data:` URI, for `proof diff`
+    Thumbnail,
+}
+
+impl Column {
+    /// The columns of the original TSV manifest.
+    pub const DEFAULT: &[Column] = &[
+        Column::Filename,
+        Column::Kind,
+        Column::Resolution,
+        Column::Format,
+        Column::Size,
+        Column::ColorSpace,
+    ];
+
+    /// The JSON default: the TSV columns plus what `proof diff` needs to
+    /// tell changed files apart.
+    pub const JSON_DEFAULT: &[Column] = &[
+        Column::Path,
+        Column::Filename,
+        Column::Kind,
+        Column::Resolution,
+        Column::Format,
+        Column::Size,
+        Column::Bytes,
+        Column::ColorSpace,
+        Column::Checksum,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Column::Filename => "Filename",
+            Column::Path => "Path",
+            Column::Kind => "Type",
+            Column::Resolution => "Resolution",
+            Column::Width => "Width",
+            Column::Height => "Height",
+            Column::Format => "Format",
+            Column::Size => "Size",
+            Column::Bytes => "Bytes",
+            Column::ColorSpace => "Color Space",
+            Column::Duration => "Duration",
+            Column::Codec => "Codec",
+            Column::Checksum => "SHA-256",
+            Column::Thumbnail => "Thumbnail",
+        }
+    }
+
+    /// Field name in JSON output.
+    fn key(self) -> &'static str {
+        match self {
+            Column::Filename => "filename",
+            Column::Path => "path",
+            Column::Kind => "kind",
+            Column::Resolution => "resolution",
+            Column::Width => "width",
+            Column::Height => "height",
+            Column::Format => "format",
+            Column::Size => "size",
+            Column::Bytes => "bytes",
+            Column::ColorSpace => "color_space",
+            Column::Duration => "duration",
+            Column::Codec => "codec",
+            Column::Checksum => "checksum",
+            Column::Thumbnail => "thumbnail",
+        }
+    }
+
+    fn value(self, asset: &Asset, root: &Path) -> Value {
+        let text = |s: Option<&String>| s.map_or(Value::Empty, |s| Value::Text(s.clone()));
+        match self {
+            Column::Filename => Value::Text(asset.filename.clone()),
+            Column::Path => Value::Text(config::relative(root, &asset.path)),
+            Column::Kind => Value::Text(asset.kind.to_string()),
+            Column::Resolution => match (asset.width, asset.height) {
+                (Some(_), Some(_)) => Value::Text(asset.resolution()),
+                _ => Value::Empty,
+            },
+            Column::Width => asset.width.map_or(Value::Empty, |w| Value::Int(w as u64)),
+            Column::Height => asset.height.map_or(Value::Empty, |h| Value::Int(h as u64)),
+            Column::Format => Value::Text(asset.format.clone()),
+            Column::Size => Value::Text(asset.human_size()),
+            Column::Bytes => Value::Int(asset.file_size),
+            Column::ColorSpace => text(asset.color_space.as_ref()),
+            Column::Duration => asset.duration.map_or(Value::Empty, Value::Float),
+            Column::Codec => text(asset.codec.as_ref()),
+            Column::Checksum => text(asset.checksum.as_ref()),
+            Column::Thumbnail => asset
+                .thumbnail_path
+                .as_deref()
+                .and_then(|thumb| crate::thumb::data_uri(thumb, THUMBNAIL_SIZE).ok())
+                .map_or(Value::Empty, Value::Text),
+        }
+    }
+}
+
+// Longest edge of previews in the `thumbnail` column, in pixels.
+const THUMBNAIL_SIZE: u32 = 240;
+
+enum Value {
+    Text(String),
+    Int(u64),
+    Float(f64),
+    Empty,
+}
+
+impl Value {
+    /// As text, with `empty` for missing values.
+    fn text<'a>(&'a self, empty: &'a str) -> std::borrow::Cow<'a, str> {
+        match self {
+            Value::Text(s) => s.into(),
+            Value::Int(n) => n.to_string().into(),
+            Value::Float(n) => n.to_string().into(),
+            Value::Empty => empty.into(),
+        }
+    }
+}
+
+impl serde::Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Text(s) => serializer.serialize_str(s),
+            Value::Int(n) => serializer.serialize_u64(*n),
+            Value::Float(n) => serializer.serialize_f64(*n),
+            Value::Empty => serializer.serialize_none(),
+        }
+    }
+}
+
+/// One asset as a JSON object, keys in column order.
+struct JsonRow<'a>(&'a [Column], &'a [Value]);
+
+impl serde::Serialize for JsonRow<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (column, value) in self.0.iter().zip(self.1) {
+            map.serialize_entry(column.key(), value)?;
+        }
+        map.end()
+    }
+}
+
+/// What to put in a manifest and how.
+#[derive(Clone)]
+pub struct Manifest {
+    pub format: Format,
+    pub columns: Vec<Column>,
+    /// Where to write it. `None` means stdout.
+    pub out: Option<PathBuf>,
+}
+
+impl Manifest {
+    /// Write the manifest for `assets`, found under `root`.
+    pub fn write(&self, assets: &[Asset], root: &Path) -> Result<()> {
+        let bytes = self.render(assets, root)?;
+        match self.out {
+            Some(ref path) => std::fs::write(path, bytes)
+                .with_context(|| format!("cannot write '{}'", path.display())),
+            None => {
+                use std::io::Write;
+                std::io::stdout().write_all(&bytes)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn render(&self, assets: &[Asset], root: &Path) -> Result<Vec<u8>> {
+        let rows: Vec<Vec<Value>> = assets
+            .iter()
+            .map(|a| self.columns.iter().map(|c| c.value(a, root)).collect())
+            .collect();
+
+        match self.format {
+            Format::Tsv => self.delimited(&rows, b'\t', "—"),
+            Format::Csv => self.delimited(&rows, b',', ""),
+            Format::Json => {
+                let objects: Vec<JsonRow> = rows.iter().map(|row| JsonRow(&self.columns, row)).collect();
+                let mut json = serde_json::to_vec_pretty(&objects)?;
+                json.push(b'\n');
+                Ok(json)
+            }
+            Format::Md => Ok(self.markdown(&rows).into_bytes()),
+            Format::Xlsx => self.xlsx(&rows),
+        }
+    }
+
+    fn delimited(&self, rows: &[Vec<Value>], delimiter: u8, empty: &str) -> Result<Vec<u8>> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(Vec::new());
+        writer.write_record(self.columns.iter().map(|c| c.title()))?;
+        for row in rows {
+            writer.write_record(row.iter().map(|v| v.text(empty).into_owned()))?;
+        }
+        Ok(writer.into_inner().map_err(|e| e.into_error())?)
+    }
+
+    fn markdown(&self, rows: &[Vec<Value>]) -> String {
+        let cell = |s: &str| s.replace('|', "\\|");
+        let mut md = String::new();
+        let titles: Vec<&str> = self.columns.iter().map(|c| c.title()).collect();
+        md.push_str(&format!("| {} |\n", titles.join(" | ")));
+        let rules: Vec<&str> = self
+            .columns
+            .iter()
+            .map(|c| match c {
+                Column::Width | Column::Height | Column::Bytes | Column::Duration => "---:",
+                _ => "---",
+            })
+            .collect();
+        md.push_str(&format!("|{}|\n", rules.join("|")));
+        for row in rows {
+            let cells: Vec<String> = row.iter().map(|v| cell(&v.text("—"))).collect();
+            md.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        md
+    }
+
+    fn xlsx(&self, rows: &[Vec<Value>]) -> Result<Vec<u8>> {
+        use rust_xlsxwriter::{Format as Style, Workbook};
+
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("Manifest")?;
+        let bold = Style::new().set_bold();
+        for (col, column) in self.columns.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, column.title(), &bold)?;
+        }
+        for (r, row) in rows.iter().enumerate() {
+            let r = r as u32 + 1;
+            for (col, value) in row.iter().enumerate() {
+                let col = col as u16;
+                match value {
+                    Value::Text(s) => sheet.write_string(r, col, s)?,
+                    Value::Int(n) => sheet.write_number(r, col, *n as f64)?,
+                    Value::Float(n) => sheet.write_number(r, col, *n)?,
+                    Value::Empty => sheet,
+                };
+            }
+        }
+        sheet.set_freeze_panes(1, 0)?;
+        sheet.autofit();
+        Ok(workbook.save_to_buffer()?)
+    }
+}
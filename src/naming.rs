@@ -0,0 +1,242 @@
+//! Output file names from a template such as
+//! `{client}_{title}_{date}_v{rev}.pdf`.
+//!
+//! | Placeholder | Value |
+//! |-------------|-------|
+//! | `{client}` | Client name |
+//! | `{title}` | Document title, or nothing |
+//! | `{date}` | Delivery date |
+//! | `{rev}` | Revision number from the delivery log |
+//! | `{files}` | Number of files in the proof |
+//! | `{folder}` | Name of the input folder |
+//!
+//! Values are slugified, so accents are folded to ASCII and punctuation
+//! becomes `-`. A placeholder with nothing in it takes a separator next to
+//! it along, so a missing title doesn't leave `__` behind.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The default, `{client}-delivery-{date}-v{rev}.pdf`.
+pub const DEFAULT_TEMPLATE: &str = "{client}-delivery-{date}-v{rev}.pdf";
+
+const PLACEHOLDERS: [&str; 6] = ["client", "title", "date", "rev", "files", "folder"];
+
+/// A checked output name template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template(String);
+
+impl Default for Template {
+    fn default() -> Self {
+        Self(DEFAULT_TEMPLATE.to_string())
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    /// Accepts only known placeholders and balanced braces.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        for piece in pieces(s) {
+            match piece {
+                Piece::Placeholder(name) if !PLACEHOLDERS.contains(&name) => {
+                    return Err(format!("unknown placeholder {{{name}}} (expected one of: {})", PLACEHOLDERS.join(", ")))
+                }
+                Piece::Unclosed => return Err(String::from("'{' without a matching '}'")),
+                _ => {}
+            }
+        }
+        if s.trim().is_empty() {
+            return Err(String::from("template is empty"));
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+/// What goes into the placeholders.
+pub struct Fields<'a> {
+    pub client: &'a str,
+    pub title: Option<&'a str>,
+    pub date: &'a str,
+    pub revision: u32,
+    pub files: usize,
+    /// The input folder, whose name fills `{folder}`.
+    pub input: &'a Path,
+}
+
+impl Template {
+    /// The file name for `fields`, with `.pdf` added if the template has
+    /// no extension of its own.
+    pub fn apply(&self, fields: &Fields) -> PathBuf {
+        let mut name = String::new();
+        // An empty placeholder at the start of a name drops the separator
+        // after it instead
+        let mut dangling = false;
+        for piece in pieces(&self.0) {
+            match piece {
+                Piece::Text(text) if dangling => name.push_str(text.trim_start_matches(['-', '_', ' '])),
+                Piece::Text(text) => name.push_str(text),
+                Piece::Placeholder(placeholder) => {
+                    let value = slugify(&value(placeholder, fields));
+                    if !value.is_empty() {
+                        name.push_str(&value);
+                    } else if name.is_empty() || name.ends_with('/') {
+                        dangling = true;
+                        continue;
+                    } else if name.ends_with(['-', '_', '.', ' ']) {
+                        name.pop();
+                    }
+                }
+                Piece::Unclosed => unreachable!("checked when parsed"),
+            }
+            dangling = false;
+        }
+
+        // Nothing before the extension, as with `{title}.pdf` and no title
+        if name.is_empty() || name.ends_with('/') || name.rsplit('/').next().is_some_and(|n| n.starts_with('.')) {
+            let at = name.rfind('/').map_or(0, |i| i + 1);
+            name.insert_str(at, "delivery");
+        }
+        let mut path = PathBuf::from(name);
+        if path.extension().is_none() {
+            path.set_extension("pdf");
+        }
+        path
+    }
+}
+
+fn value(placeholder: &str, fields: &Fields) -> String {
+    match placeholder {
+        "client" => fields.client.to_string(),
+        "title" => fields.title.unwrap_or_default().to_string(),
+        "date" => fields.date.to_string(),
+        "rev" => fields.revision.to_string(),
+        "files" => fields.files.to_string(),
+        "folder" => folder_name(fields.input),
+        _ => unreachable!("checked when parsed"),
+    }
+}
+
+/// The folder's own name, even when it was given as `.`.
+fn folder_name(input: &Path) -> String {
+    let named = |p: &Path| p.file_name().map(|n| n.to_string_lossy().into_owned());
+    named(input)
+        .or_else(|| std::fs::canonicalize(input).ok().as_deref().and_then(named))
+        .unwrap_or_default()
+}
+
+enum Piece<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+    Unclosed,
+}
+
+fn pieces(template: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            pieces.push(Piece::Text(&rest[..open]));
+        }
+        let Some(close) = rest[open..].find('}') else {
+            pieces.push(Piece::Unclosed);
+            return pieces;
+        };
+        pieces.push(Piece::Placeholder(&rest[open + 1..open + close]));
+        rest = &rest[open + close + 1..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+    pieces
+}
+
+/// Lowercase ASCII letters and digits, with every other run of characters
+/// turned into a single `-`: "Renée & Co." becomes `renee-co`.
+pub fn slugify(text: &str) -> String {
+    let ascii = deunicode::deunicode(text);
+    let mut slug = String::with_capacity(ascii.len());
+    for c in ascii.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Where a proof goes: `output` if one was given, otherwise `template`
+/// filled in from `fields`, numbered if that file exists already.
+pub fn output_for(output: Option<&Path>, template: &Template, fields: &Fields) -> PathBuf {
+    match output {
+        Some(output) => output.to_path_buf(),
+        None => unused(template.apply(fields)),
+    }
+}
+
+/// `path`, or the first of `name-2.pdf`, `name-3.pdf`, … that doesn't exist
+/// yet, so an earlier delivery is never overwritten.
+pub fn unused(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+    (2..)
+        .map(|n| {
+            let name = match extension {
+                Some(ref ext) => format!("{stem}-{n}.{ext}"),
+                None => format!("{stem}-{n}"),
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("some number is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(template: &str, client: &str, title: Option<&str>) -> String {
+        let template: Template = template.parse().unwrap();
+        let fields = Fields { client, title, date: "2025-03-01", revision: 2, files: 12, input: Path::new("finals") };
+        template.apply(&fields).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn empty_title_takes_a_separator_along() {
+        assert_eq!(name("{title}_{client}_v{rev}.pdf", "Acme", None), "acme_v2.pdf");
+        assert_eq!(name("{client}_{title}_{date}.pdf", "Acme", None), "acme_2025-03-01.pdf");
+        assert_eq!(name("{client}_{date}_{title}.pdf", "Acme", None), "acme_2025-03-01.pdf");
+        assert_eq!(name("{client}_{title}_{date}.pdf", "Acme", Some("SS26")), "acme_ss26_2025-03-01.pdf");
+        assert_eq!(name("{title}.pdf", "Acme", None), "delivery.pdf");
+    }
+
+    #[test]
+    fn slash_in_template_makes_folders() {
+        assert_eq!(name("{client}/{title}-{files}", "Acme", None), "acme/12.pdf");
+        assert_eq!(name("{client}/{title}.pdf", "Acme", None), "acme/delivery.pdf");
+        assert_eq!(name("{folder}/{client}.pdf", "Acme", None), "finals/acme.pdf");
+        // But not in values
+        assert_eq!(name("{client}.pdf", "AC/DC", None), "ac-dc.pdf");
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert!("{client}_{version}.pdf".parse::<Template>().is_err());
+        assert!("{client.pdf".parse::<Template>().is_err());
+        assert!(" ".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn slugify_folds_to_ascii() {
+        assert_eq!(slugify("Renée & Co."), "renee-co");
+        assert_eq!(slugify("Ærø Straße"), "aero-strasse");
+        assert_eq!(slugify("Привет, мир"), "privet-mir");
+        assert_eq!(slugify("北京"), "bei-jing");
+        assert_eq!(slugify("--Spring  2026--"), "spring-2026");
+        assert_eq!(slugify("!!!"), "");
+    }
+}
@@ -0,0 +1,196 @@
+//! The delivery as one ZIP: every asset at its path in the input folder,
+//! the proof PDF, a JSON manifest, a `SHA256SUMS` list and an optional
+//! README.
+//!
+//! Assets are stored as they are, since images and video don't compress
+//! any further, and are hashed on the way in. A file that no longer matches
+//! the checksum taken when it was processed fails the package rather than
+//! going out unproofed. Once written, the archive is read back and every
+//! entry checked against its hash before it replaces anything at the
+//! destination.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+use zip::CompressionMethod;
+
+use crate::cancel::Cancel;
+use crate::dupes::hashed_copy;
+use crate::manifest::{Column, Format, Manifest};
+use crate::scan::Asset;
+
+/// Name of the JSON manifest in the archive. It has the columns `proof
+/// diff` needs, so it can be compared with the next delivery.
+pub const MANIFEST: &str = "manifest.json";
+
+/// Name of the hash list in the archive, in `sha256sum` format.
+pub const CHECKSUMS: &str = "SHA256SUMS";
+
+const MANIFEST_COLUMNS: &[Column] = &[
+    Column::Path,
+    Column::Filename,
+    Column::Kind,
+    Column::Resolution,
+    Column::Format,
+    Column::Size,
+    Column::Bytes,
+    Column::ColorSpace,
+    Column::Duration,
+    Column::Codec,
+    Column::Checksum,
+    Column::Thumbnail,
+];
+
+/// What goes in the package.
+pub struct Contents<'a> {
+    pub assets: &'a [Asset],
+    /// The input folder the assets were found in.
+    pub root: &'a Path,
+    /// The rendered proof.
+    pub proof: &'a Path,
+    pub readme: Option<&'a Path>,
+}
+
+/// A written and verified package.
+#[derive(Debug, Clone)]
+pub struct Packaged {
+    /// Entries in the archive, `SHA256SUMS` included.
+    pub files: usize,
+    /// Size of the archive.
+    pub bytes: u64,
+}
+
+enum Source<'a> {
+    File { path: &'a Path, checksum: Option<&'a str> },
+    Bytes(Vec<u8>),
+}
+
+/// Write `contents` to a ZIP at `path` and check it reads back intact.
+pub fn write(path: &Path, contents: &Contents, cancel: &Cancel) -> Result<Packaged> {
+    let mut entries: Vec<(String, Source)> = contents
+        .assets
+        .iter()
+        .map(|a| {
+            let source = Source::File { path: &a.path, checksum: a.checksum.as_deref() };
+            (crate::config::relative(contents.root, &a.path), source)
+        })
+        .collect();
+    entries.push((file_name(contents.proof)?, Source::File { path: contents.proof, checksum: None }));
+    if let Some(readme) = contents.readme {
+        entries.push((file_name(readme)?, Source::File { path: readme, checksum: None }));
+    }
+    let manifest = Manifest { format: Format::Json, columns: MANIFEST_COLUMNS.to_vec(), out: None };
+    entries.push((MANIFEST.to_string(), Source::Bytes(manifest.render(contents.assets, contents.root)?)));
+
+    let mut seen = std::collections::HashSet::new();
+    for (name, _) in &entries {
+        if !seen.insert(name.as_str()) || name == CHECKSUMS {
+            bail!("two files would be packaged as '{name}'");
+        }
+    }
+
+    // Build next to the destination so a failed or cancelled package never
+    // leaves half an archive behind
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("cannot write to '{}'", dir.display()))?;
+    let mut zip = zip::ZipWriter::new(file.as_file_mut());
+    // Files made for the package are dated now
+    let mut generated = SimpleFileOptions::default();
+    if let Some(now) = modified(std::time::SystemTime::now()) {
+        generated = generated.last_modified_time(now);
+    }
+
+    let mut sums = Vec::with_capacity(entries.len() + 1);
+    for (name, source) in &entries {
+        cancel.check()?;
+        let sum = match source {
+            Source::File { path, checksum } => {
+                let meta = std::fs::metadata(path).with_context(|| format!("cannot read '{}'", path.display()))?;
+                let method = match checksum {
+                    // Assets are compressed already
+                    Some(_) => CompressionMethod::Stored,
+                    None => CompressionMethod::Deflated,
+                };
+                let mut options = SimpleFileOptions::default()
+                    .compression_method(method)
+                    .large_file(meta.len() >= u32::MAX as u64);
+                if let Some(time) = meta.modified().ok().and_then(modified) {
+                    options = options.last_modified_time(time);
+                }
+                zip.start_file(name.as_str(), options)?;
+                let mut reader = std::fs::File::open(path).with_context(|| format!("cannot read '{}'", path.display()))?;
+                let sum = hashed_copy(&mut reader, &mut zip, cancel).with_context(|| format!("cannot package '{}'", path.display()))?;
+                if checksum.is_some_and(|expected| expected != sum) {
+                    bail!("'{}' changed after it was processed; run again to proof the new version", path.display());
+                }
+                sum
+            }
+            Source::Bytes(bytes) => {
+                zip.start_file(name.as_str(), generated)?;
+                hashed_copy(&mut bytes.as_slice(), &mut zip, cancel)?
+            }
+        };
+        sums.push((name.clone(), sum));
+    }
+
+    let list: String = sums.iter().map(|(name, sum)| format!("{sum}  {name}\n")).collect();
+    zip.start_file(CHECKSUMS, generated)?;
+    zip.write_all(list.as_bytes())?;
+    sums.push((CHECKSUMS.to_string(), format!("{:x}", Sha256::digest(list.as_bytes()))));
+    zip.finish()?;
+    file.as_file().sync_all()?;
+
+    verify(file.path(), &sums, cancel)?;
+    let bytes = file.as_file().metadata()?.len();
+    file.persist(path).with_context(|| format!("cannot write '{}'", path.display()))?;
+    Ok(Packaged { files: sums.len(), bytes })
+}
+
+/// Read every entry of the archive at `path` back and compare it with
+/// `sums`. The ZIP reader checks each entry's CRC as well.
+fn verify(path: &Path, sums: &[(String, String)], cancel: &Cancel) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?).context("package verification failed")?;
+    if archive.len() != sums.len() {
+        bail!("package verification failed: {} entries written, {} read back", sums.len(), archive.len());
+    }
+    for (name, expected) in sums {
+        let mut entry = archive
+            .by_name(name)
+            .with_context(|| format!("package verification failed: '{name}' is missing"))?;
+        let sum = hashed_copy(&mut entry, &mut std::io::sink(), cancel)
+            .with_context(|| format!("package verification failed: cannot read '{name}' back"))?;
+        if &sum != expected {
+            bail!("package verification failed: '{name}' doesn't match what was written");
+        }
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .with_context(|| format!("'{}' isn't a file", path.display()))
+}
+
+/// A file's modification time as a ZIP timestamp, in local time as ZIP
+/// tools expect. `None` before 1980, which ZIP can't represent.
+fn modified(time: std::time::SystemTime) -> Option<zip::DateTime> {
+    use chrono::{Datelike, Timelike};
+    let local = chrono::DateTime::<chrono::Local>::from(time);
+    zip::DateTime::from_date_and_time(
+        u16::try_from(local.year()).ok()?,
+        local.month() as u8,
+        local.day() as u8,
+        local.hour() as u8,
+        local.minute() as u8,
+        local.second() as u8,
+    )
+    .ok()
+}
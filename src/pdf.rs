@@ -1,74 +1,191 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
 use serde::Serialize;
 
+use crate::cancel::Cancel;
+use crate::changes::{Changes, Listed};
+use crate::error::{RenderError, WriteContext};
 use crate::scan::{Asset, AssetKind};
 
+/// What goes on the proof besides the assets. Start from `new()` and
+/// change what you need, since fields are added over time.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct PdfConfig {
     pub client: String,
     pub title: Option<String>,
     pub date: String,
     pub columns: u8,
     pub auto_orient: bool,
+    /// Source path of the image shown on the cover page.
+    pub hero: Option<PathBuf>,
+    /// Leave byte-for-byte copies of a file off the contact sheet.
+    pub skip_duplicates: bool,
+    /// Adds a "Changes since …" page.
+    pub changes: Option<Changes>,
+    /// Printed on the cover as "Delivery 3".
+    pub revision: Option<u32>,
 }
 
-pub fn render(assets: &[Asset], config: &PdfConfig, output: &Path) -> Result<()> {
+impl PdfConfig {
+    /// Four columns, no title, hero, changes page or revision.
+    pub fn new(client: impl Into<String>, date: impl Into<String>) -> Self {
+        Self {
+            client: client.into(),
+            title: None,
+            date: date.into(),
+            columns: 4,
+            auto_orient: false,
+            hero: None,
+            skip_duplicates: false,
+            changes: None,
+            revision: None,
+        }
+    }
+}
+
+// Longest edge of the cover image, in pixels.
+const HERO_SIZE: u32 = 1600;
+
+// Page geometry from templates/delivery-proof.typ, in points.
+const CONTENT_WIDTH: f64 = (8.5 - 2.0 * 0.6) * 72.0;
+const COLUMN_GUTTER: f64 = 8.0;
+const CELL_HEIGHT: f64 = 110.0;
+const THUMB_DPI: f64 = 200.0;
+
+/// `{client}-delivery-{date}-v{revision}.pdf` in the working directory,
+/// from the default output name template.
+pub fn default_output(client: &str, date: &str, revision: u32) -> PathBuf {
+    crate::naming::Template::default().apply(&crate::naming::Fields {
+        client,
+        title: None,
+        date,
+        revision,
+        files: 0,
+        input: Path::new("."),
+    })
+}
+
+/// Thumbnail size that prints at ~200dpi in a contact sheet cell for the
+/// given column count.
+pub fn default_thumb_size(columns: u8) -> u32 {
+    let columns = columns.max(1) as f64;
+    let cell_width = (CONTENT_WIDTH - COLUMN_GUTTER * (columns - 1.0)) / columns;
+    let longest = cell_width.max(CELL_HEIGHT);
+    (longest / 72.0 * THUMB_DPI).ceil() as u32
+}
+
+/// Render the proof of `assets` to `output`, making its folder if need be.
+pub fn render(assets: &[Asset], config: &PdfConfig, output: &Path, cancel: &Cancel) -> Result<(), RenderError> {
     check_typst()?;
+    cancel.check()?;
+
+    let build_dir = tempfile::tempdir().writing(&std::env::temp_dir())?;
+    let build_dir = build_dir.path();
+    let hero = prepare_hero(assets, config, build_dir);
+
+    let data = build_data(assets, config, hero);
+    let json = serde_json::to_string_pretty(&data).expect("template data always serializes");
 
-    let data = build_data(assets, config);
-    let json = serde_json::to_string_pretty(&data)?;
+    let write = |name: &str, contents: &[u8]| {
+        let path = build_dir.join(name);
+        std::fs::write(&path, contents).writing(&path)
+    };
+    write("template.typ", include_bytes!("../templates/delivery-proof.typ"))?;
+    write("data.json", json.as_bytes())?;
+
+    copy_thumbs(assets, build_dir)?;
+    write_previous_thumbs(config, build_dir)?;
+
+    // Bundle Apercu Pro fonts into build dir
+    let fonts_dir = build_dir.join("fonts");
+    std::fs::create_dir_all(&fonts_dir).writing(&fonts_dir)?;
+    write("fonts/Apercu Pro Regular.ttf", include_bytes!("../fonts/Apercu Pro Regular.ttf"))?;
+    write("fonts/Apercu Pro Light.ttf", include_bytes!("../fonts/Apercu Pro Light.ttf"))?;
+    write("fonts/Apercu Pro Medium.ttf", include_bytes!("../fonts/Apercu Pro Medium.ttf"))?;
+    write("fonts/Apercu Pro Bold.ttf", include_bytes!("../fonts/Apercu Pro Bold.ttf"))?;
+
+    // Compile inside the build dir and move the result into place, so a
+    // cancelled or failed run never leaves a half-written PDF behind.
+    let compiled = cancel.output(
+        std::process::Command::new("typst")
+            .arg("compile")
+            .arg("--font-path").arg("fonts")
+            .arg("template.typ")
+            .arg("output.pdf")
+            .current_dir(build_dir),
+    );
+    let compiled = match compiled {
+        Ok(compiled) => compiled,
+        Err(e) if crate::cancel::is_cancelled(&e) => return Err(RenderError::Cancelled),
+        Err(e) => {
+            let source = match e.downcast::<std::io::Error>() {
+                Ok(io) => io,
+                Err(e) => std::io::Error::other(format!("{e:#}")),
+            };
+            return Err(spawn_error(source));
+        }
+    };
+
+    if !compiled.status.success() {
+        return Err(RenderError::Typst {
+            code: compiled.status.code(),
+            diagnostics: String::from_utf8_lossy(&compiled.stderr).into_owned(),
+        });
+    }
+
+    // Output templates can put the proof in a folder of its own
+    if let Some(dir) = output.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).writing(dir)?;
+    }
+    let built = build_dir.join("output.pdf");
+    if std::fs::rename(&built, output).is_err() {
+        std::fs::copy(&built, output).writing(output)?;
+    }
 
-    let build_dir = tempfile::tempdir()?;
-    std::fs::write(
-        build_dir.path().join("template.typ"),
-        include_str!("../templates/delivery-proof.typ"),
-    )?;
-    std::fs::write(build_dir.path().join("data.json"), &json)?;
+    Ok(())
+}
 
-    let thumbs_dir = build_dir.path().join("thumbs");
-    std::fs::create_dir_all(&thumbs_dir)?;
+/// Copy thumbnails and filmstrip frames into `dir/thumbs`, where `thumb_ref`
+/// points, and return the copies.
+pub(crate) fn copy_thumbs(assets: &[Asset], dir: &Path) -> Result<Vec<PathBuf>, RenderError> {
+    let thumbs_dir = dir.join("thumbs");
+    std::fs::create_dir_all(&thumbs_dir).writing(&thumbs_dir)?;
+    let mut written = Vec::new();
     for asset in assets {
-        if let Some(ref thumb) = asset.thumbnail_path {
+        let frames = asset.filmstrip.iter().map(|f| &f.path);
+        for thumb in asset.thumbnail_path.iter().chain(frames) {
             if let Some(name) = thumb.file_name() {
-                std::fs::copy(thumb, thumbs_dir.join(name))?;
+                let to = thumbs_dir.join(name);
+                std::fs::copy(thumb, &to).writing(&to)?;
+                written.push(to);
             }
         }
     }
+    Ok(written)
+}
 
-    // Bundle Apercu Pro fonts into build dir
-    let fonts_dir = build_dir.path().join("fonts");
-    std::fs::create_dir_all(&fonts_dir)?;
-    std::fs::write(fonts_dir.join("Apercu Pro Regular.ttf"), include_bytes!("../fonts/Apercu Pro Regular.ttf"))?;
-    std::fs::write(fonts_dir.join("Apercu Pro Light.ttf"), include_bytes!("../fonts/Apercu Pro Light.ttf"))?;
-    std::fs::write(fonts_dir.join("Apercu Pro Medium.ttf"), include_bytes!("../fonts/Apercu Pro Medium.ttf"))?;
-    std::fs::write(fonts_dir.join("Apercu Pro Bold.ttf"), include_bytes!("../fonts/Apercu Pro Bold.ttf"))?;
-
-    let abs_output = if output.is_absolute() {
-        output.to_path_buf()
-    } else {
-        std::env::current_dir()?.join(output)
+/// Write the thumbnails a previous manifest kept of files that have since
+/// been replaced or removed, where `previous_ref` points, and return them.
+pub(crate) fn write_previous_thumbs(config: &PdfConfig, dir: &Path) -> Result<Vec<PathBuf>, RenderError> {
+    let Some(ref changes) = config.changes else {
+        return Ok(Vec::new());
     };
-
-    let status = std::process::Command::new("typst")
-        .arg("compile")
-        .arg("--font-path").arg("fonts")
-        .arg("template.typ")
-        .arg(&abs_output)
-        .current_dir(build_dir.path())
-        .status()
-        .context("failed to run typst")?;
-
-    anyhow::ensure!(
-        status.success(),
-        "typst compile failed (exit code: {:?})",
-        status.code()
-    );
-
-    Ok(())
+    let thumbs_dir = dir.join("thumbs");
+    std::fs::create_dir_all(&thumbs_dir).writing(&thumbs_dir)?;
+    let listed = changes.removed.iter().chain(changes.replaced.iter().map(|(_, l)| l));
+    let mut written = Vec::new();
+    for file in listed {
+        if let (Some(jpeg), Some(name)) = (&file.thumbnail, previous_ref(file)) {
+            let to = dir.join(name);
+            std::fs::write(&to, jpeg).writing(&to)?;
+            written.push(to);
+        }
+    }
+    Ok(written)
 }
 
-fn check_typst() -> Result<()> {
+fn check_typst() -> Result<(), RenderError> {
     let status = std::process::Command::new("typst")
         .arg("--version")
         .stdout(std::process::Stdio::null())
@@ -77,60 +194,250 @@ fn check_typst() -> Result<()> {
 
     match status {
         Ok(s) if s.success() => Ok(()),
-        _ => anyhow::bail!("typst not found — install with: brew install typst"),
+        Ok(s) => Err(RenderError::Typst { code: s.code(), diagnostics: String::new() }),
+        Err(e) => Err(spawn_error(e)),
     }
 }
 
+fn spawn_error(source: std::io::Error) -> RenderError {
+    match source.kind() {
+        std::io::ErrorKind::NotFound => RenderError::TypstMissing,
+        _ => RenderError::TypstSpawn { source },
+    }
+}
+
+/// What the templates are filled from: `data.json` for Typst, and the
+/// embedded data of the HTML gallery.
+#[derive(Serialize)]
+pub(crate) struct TemplateData {
+    pub client: String,
+    pub title: Option<String>,
+    pub date: String,
+    pub revision: Option<u32>,
+    pub columns: u8,
+    pub auto_orient: bool,
+    pub hero: Option<String>,
+    pub summary: Summary,
+    pub assets: Vec<AssetEntry>,
+    /// Only when the assets were deep checked.
+    pub qc: Option<QcReport>,
+    pub duplicates: Vec<DuplicateGroup>,
+    pub changes: Option<ChangesPage>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ChangesPage {
+    pub since: String,
+    pub summary: String,
+    pub added: Vec<Change>,
+    pub removed: Vec<Change>,
+    pub replaced: Vec<Change>,
+    pub renamed: Vec<Change>,
+}
+
+/// A file before and after, with one side missing for added and removed
+/// files.
+#[derive(Serialize)]
+pub(crate) struct Change {
+    pub before: Option<ChangedFile>,
+    pub after: Option<ChangedFile>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ChangedFile {
+    pub name: String,
+    /// Resolution and size, where known.
+    pub detail: String,
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Summary {
+    pub total_files: usize,
+    pub total_size: String,
+    pub image_count: usize,
+    pub video_count: usize,
+    /// Unreadable files in the proof, counted by what went wrong.
+    pub failures: Vec<FailureCount>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct FailureCount {
+    pub label: &'static str,
+    pub count: usize,
+}
+
+#[derive(Serialize)]
+pub(crate) struct QcReport {
+    /// Files decoded in full.
+    pub checked: usize,
+    /// Files with problems, of those checked.
+    pub problems: usize,
+    /// Files the check couldn't run on.
+    pub unchecked: usize,
+    pub issues: Vec<QcEntry>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct QcEntry {
+    pub filename: String,
+    pub label: &'static str,
+    pub message: String,
+    /// A problem with the file, rather than the check not running.
+    pub problem: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DuplicateGroup {
+    pub label: &'static str,
+    pub members: Vec<DuplicateMember>,
+}
+
 #[derive(Serialize)]
-struct TemplateData {
-    client: String,
-    title: Option<String>,
-    date: String,
-    columns: u8,
-    auto_orient: bool,
-    summary: Summary,
-    assets: Vec<AssetEntry>,
+pub(crate) struct DuplicateMember {
+    pub filename: String,
+    pub resolution: String,
+    pub human_size: String,
+    pub thumbnail: Option<String>,
+    /// Left off the contact sheet by `skip_duplicates`.
+    pub skipped: bool,
 }
 
 #[derive(Serialize)]
-struct Summary {
-    total_files: usize,
-    total_size: String,
-    image_count: usize,
-    video_count: usize,
+pub(crate) struct AssetEntry {
+    pub filename: String,
+    pub kind: String,
+    pub resolution: String,
+    pub format: String,
+    pub human_size: String,
+    pub thumbnail: Option<String>,
+    pub color_space: Option<String>,
+    pub duration: Option<String>,
+    pub filmstrip: Vec<FilmstripEntry>,
+    pub unreadable: bool,
+    /// The copy shown instead of this one, when duplicates are skipped.
+    pub duplicate_of: Option<String>,
+    /// Raw values for sorting, next to the formatted ones above.
+    pub bytes: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub seconds: Option<f64>,
+    /// Large image or video clip, filled in by the HTML gallery only.
+    pub preview: Option<String>,
 }
 
 #[derive(Serialize)]
-struct AssetEntry {
-    filename: String,
-    kind: String,
-    resolution: String,
-    format: String,
-    human_size: String,
-    thumbnail: Option<String>,
-    color_space: Option<String>,
-    duration: Option<String>,
-}
-
-fn build_data(assets: &[Asset], config: &PdfConfig) -> TemplateData {
+pub(crate) struct FilmstripEntry {
+    pub image: String,
+    pub timecode: String,
+}
+
+/// Write a large preview of the hero image into `build_dir`, falling back
+/// to its thumbnail for videos or if decoding fails.
+pub(crate) fn prepare_hero(assets: &[Asset], config: &PdfConfig, build_dir: &Path) -> Option<String> {
+    let hero = config.hero.as_deref()?;
+    let asset = assets.iter().find(|a| a.path == hero)?;
+
+    if asset.kind == AssetKind::Image {
+        let out = build_dir.join("hero.jpg");
+        let written = crate::scan::preview(&asset.path, HERO_SIZE, config.auto_orient)
+            .and_then(|img| crate::thumb::save_jpeg(&img, &out, 90));
+        if written.is_ok() {
+            return Some(String::from("hero.jpg"));
+        }
+    }
+
+    asset.thumbnail_path.as_deref().and_then(thumb_ref)
+}
+
+pub(crate) fn build_data(assets: &[Asset], config: &PdfConfig, hero: Option<String>) -> TemplateData {
     let total_size: u64 = assets.iter().map(|a| a.file_size).sum();
     let image_count = assets.iter().filter(|a| a.kind == AssetKind::Image).count();
     let video_count = assets.iter().filter(|a| a.kind == AssetKind::Video).count();
+    let mut failures = std::collections::BTreeMap::new();
+    for error in assets.iter().filter_map(|a| a.error.as_ref()) {
+        *failures.entry(error.kind()).or_insert(0) += 1;
+    }
+
+    let changes = config.changes.as_ref().map(|changes| {
+        let now = |index: usize| {
+            let a = &assets[index];
+            Some(ChangedFile {
+                name: a.filename.clone(),
+                detail: format!("{} · {}", a.resolution(), a.human_size()),
+                thumbnail: a.thumbnail_path.as_deref().and_then(thumb_ref),
+            })
+        };
+        let before = |file: &Listed| {
+            let size = file.bytes.map(|b| humansize::format_size(b, humansize::BINARY));
+            let detail: Vec<String> = file.resolution.iter().cloned().chain(size).collect();
+            Some(ChangedFile {
+                name: file.name.clone(),
+                detail: detail.join(" · "),
+                thumbnail: previous_ref(file),
+            })
+        };
+        ChangesPage {
+            since: changes.since.clone(),
+            summary: changes.describe(),
+            added: changes.added.iter().map(|&i| Change { before: None, after: now(i) }).collect(),
+            removed: changes.removed.iter().map(|f| Change { before: before(f), after: None }).collect(),
+            replaced: changes
+                .replaced
+                .iter()
+                .map(|(i, f)| Change { before: before(f), after: now(*i) })
+                .collect(),
+            renamed: changes
+                .renamed
+                .iter()
+                .map(|(i, f)| Change { before: before(f), after: now(*i) })
+                .collect(),
+        }
+    });
+
+    let groups = crate::dupes::find(assets);
+    let mut duplicate_of = vec![None; assets.len()];
+    if config.skip_duplicates {
+        for group in &groups {
+            for &(copy, original) in &group.copies {
+                duplicate_of[copy] = Some(assets[original].filename.clone());
+            }
+        }
+    }
+    let duplicates = groups
+        .iter()
+        .map(|group| DuplicateGroup {
+            label: group.matched.label(),
+            members: group
+                .members
+                .iter()
+                .map(|&m| DuplicateMember {
+                    filename: assets[m].filename.clone(),
+                    resolution: assets[m].resolution(),
+                    human_size: assets[m].human_size(),
+                    thumbnail: assets[m].thumbnail_path.as_deref().and_then(thumb_ref),
+                    skipped: duplicate_of[m].is_some(),
+                })
+                .collect(),
+        })
+        .collect();
 
     let entries = assets
         .iter()
-        .map(|a| {
-            let thumbnail = a
-                .thumbnail_path
-                .as_ref()
-                .and_then(|p| p.file_name())
-                .map(|name| format!("thumbs/{}", name.to_string_lossy()));
-
-            let duration = a.duration.map(|d| {
-                let mins = (d / 60.0).floor() as u64;
-                let secs = (d % 60.0).floor() as u64;
-                format!("{}:{:02}", mins, secs)
-            });
+        .zip(duplicate_of)
+        .map(|(a, duplicate_of)| {
+            let thumbnail = a.thumbnail_path.as_deref().and_then(thumb_ref);
+            let duration = a.duration.map(timecode);
+            let filmstrip = a
+                .filmstrip
+                .iter()
+                .filter_map(|f| {
+                    Some(FilmstripEntry {
+                        image: thumb_ref(&f.path)?,
+                        timecode: timecode(f.time),
+                    })
+                })
+                .collect();
 
             AssetEntry {
                 filename: a.filename.clone(),
@@ -141,22 +448,75 @@ fn build_data(assets: &[Asset], config: &PdfConfig) -> TemplateData {
                 thumbnail,
                 color_space: a.color_space.clone(),
                 duration,
+                filmstrip,
+                unreadable: a.error.is_some(),
+                duplicate_of,
+                bytes: a.file_size,
+                width: a.width,
+                height: a.height,
+                seconds: a.duration,
+                preview: None,
             }
         })
         .collect();
 
+    let checked: Vec<&Asset> = assets.iter().filter(|a| a.qc.is_some()).collect();
+    let qc = (!checked.is_empty()).then(|| QcReport {
+        checked: checked.iter().filter(|a| !a.is_unchecked()).count(),
+        problems: checked.iter().filter(|a| a.has_problems()).count(),
+        unchecked: checked.iter().filter(|a| a.is_unchecked()).count(),
+        issues: checked
+            .iter()
+            .flat_map(|a| {
+                a.issues().iter().map(|issue| QcEntry {
+                    filename: a.filename.clone(),
+                    label: issue.kind.label(),
+                    message: issue.message.clone(),
+                    problem: issue.kind.is_problem(),
+                })
+            })
+            .collect(),
+    });
+
     TemplateData {
         client: config.client.clone(),
         title: config.title.clone(),
         date: config.date.clone(),
+        revision: config.revision,
         columns: config.columns,
         auto_orient: config.auto_orient,
+        hero,
         summary: Summary {
             total_files: assets.len(),
             total_size: humansize::format_size(total_size, humansize::BINARY),
             image_count,
             video_count,
+            failures: failures
+                .into_iter()
+                .map(|(kind, count)| FailureCount { label: kind.label(), count })
+                .collect(),
         },
         assets: entries,
+        qc,
+        duplicates,
+        changes,
     }
 }
+
+/// Path of a generated image relative to the Typst build directory or
+/// gallery folder.
+fn thumb_ref(path: &Path) -> Option<String> {
+    path.file_name()
+        .map(|name| format!("thumbs/{}", name.to_string_lossy()))
+}
+
+/// Where the thumbnail a previous manifest kept of `file` is written.
+fn previous_ref(file: &Listed) -> Option<String> {
+    file.thumbnail.as_ref().map(|_| format!("thumbs/previous-{:04}.jpg", file.index))
+}
+
+fn timecode(secs: f64) -> String {
+    let mins = (secs / 60.0).floor() as u64;
+    let secs = (secs % 60.0).floor() as u64;
+    format!("{}:{:02}", mins, secs)
+}
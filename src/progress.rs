@@ -0,0 +1,197 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use proof::error::ErrorKind;
+use proof::scan::{Asset, AssetKind, Progress};
+use proof::{find_duplicates, DuplicateMatch};
+
+/// Bumped whenever an event or field changes meaning or goes away. New
+/// events and fields may be added without a bump.
+///
+/// 2: an asset's `format` is the container found in the file, such as
+/// `JPEG`, rather than its uppercased extension.
+pub const SCHEMA: u32 = 2;
+
+/// One line of `--progress=json` output on stderr.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Start {
+        schema: u32,
+        version: &'static str,
+        input: String,
+    },
+    /// Watch mode started another pass over the input.
+    Scanning,
+    Found {
+        index: usize,
+        path: String,
+        kind: AssetKind,
+    },
+    ScanDone {
+        total: usize,
+        images: usize,
+        videos: usize,
+    },
+    Processing {
+        index: usize,
+        path: String,
+    },
+    Processed {
+        index: usize,
+        path: String,
+        asset: &'a Asset,
+    },
+    Failed {
+        index: usize,
+        path: String,
+        kind: ErrorKind,
+        error: String,
+    },
+    Rendering,
+    Done {
+        /// Missing with `--manifest-only`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+        /// Gallery folder, only with `--html`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        html: Option<String>,
+        /// ZIP, only with `--package`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        package: Option<String>,
+        /// Only with `--manifest-out`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        manifest: Option<String>,
+        /// Number of the delivery in the log. Missing with `--manifest-only`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        revision: Option<u32>,
+        summary: Summary,
+    },
+    /// Watch mode found nothing that changes the proof.
+    Unchanged,
+    /// Something went wrong that doesn't stop the run.
+    Warning {
+        message: String,
+    },
+    /// `code` is the process exit code when the error ends the run, and
+    /// missing when watch mode carries on.
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        code: Option<i32>,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub total_files: usize,
+    /// In bytes.
+    pub total_size: u64,
+    pub image_count: usize,
+    pub video_count: usize,
+    pub skipped: usize,
+    /// Files with integrity problems, only with `--deep-check`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qc_issues: Option<usize>,
+    /// Files the deep check couldn't run on, only with `--deep-check`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qc_unchecked: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<Duplicates>,
+}
+
+/// Filenames of the files that are the same picture.
+#[derive(Debug, Clone, Serialize)]
+pub struct Duplicates {
+    #[serde(rename = "match")]
+    pub matched: DuplicateMatch,
+    pub files: Vec<String>,
+}
+
+impl Summary {
+    pub fn new(assets: &[Asset], skipped: usize) -> Self {
+        let deep_checked = assets.iter().any(|a| a.qc.is_some());
+        Self {
+            total_files: assets.len(),
+            total_size: assets.iter().map(|a| a.file_size).sum(),
+            image_count: assets.iter().filter(|a| a.kind == AssetKind::Image).count(),
+            video_count: assets.iter().filter(|a| a.kind == AssetKind::Video).count(),
+            skipped,
+            qc_issues: deep_checked.then(|| assets.iter().filter(|a| a.has_problems()).count()),
+            qc_unchecked: deep_checked.then(|| assets.iter().filter(|a| a.is_unchecked()).count()),
+            duplicates: find_duplicates(assets)
+                .into_iter()
+                .map(|group| Duplicates {
+                    matched: group.matched,
+                    files: group.members.iter().map(|&m| assets[m].filename.clone()).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Sends either human-readable lines or JSON events to stderr, never both.
+#[derive(Clone, Copy)]
+pub struct Reporter {
+    pub json: bool,
+}
+
+impl Reporter {
+    /// A line for people, dropped in JSON mode.
+    pub fn say(&self, text: impl std::fmt::Display) {
+        if !self.json {
+            eprintln!("{text}");
+        }
+    }
+
+    /// An event for machines, dropped in text mode.
+    pub fn emit(&self, event: Event) {
+        if !self.json {
+            return;
+        }
+        // One write per line so events from worker threads don't interleave
+        let mut line = serde_json::to_vec(&event).expect("events always serialize");
+        line.push(b'\n');
+        let _ = std::io::stderr().lock().write_all(&line);
+    }
+
+    pub fn found(&self, found: &[(PathBuf, AssetKind)]) {
+        for (index, (path, kind)) in found.iter().enumerate() {
+            self.emit(Event::Found { index, path: display(path), kind: *kind });
+        }
+        let images = found.iter().filter(|(_, k)| *k == AssetKind::Image).count();
+        self.emit(Event::ScanDone {
+            total: found.len(),
+            images,
+            videos: found.len() - images,
+        });
+    }
+
+    /// Forward per-file progress from `scan::process_all`.
+    pub fn progress(&self, found: &[(PathBuf, AssetKind)], progress: &Progress) {
+        let path = |index: usize| found.get(index).map(|(p, _)| display(p)).unwrap_or_default();
+        self.emit(match progress {
+            Progress::Processing { index } => Event::Processing {
+                index: *index,
+                path: path(*index),
+            },
+            Progress::Processed { index, asset } => Event::Processed {
+                index: *index,
+                path: path(*index),
+                asset,
+            },
+            Progress::Failed { index, error } => Event::Failed {
+                index: *index,
+                path: path(*index),
+                kind: error.kind(),
+                error: error.to_string(),
+            },
+        });
+    }
+}
+
+pub fn display(path: &Path) -> String {
+    path.display().to_string()
+}
@@ -0,0 +1,178 @@
+//! Integrity checks for `--deep-check`, which decode every pixel and frame.
+//!
+//! Processing only reads as much of a file as the thumbnail needs. A JPEG
+//! with an embedded preview is barely touched and a video is only probed,
+//! so a file cut short by an interrupted copy can look fine until someone
+//! opens it.
+
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::cancel::{Cancel, Cancelled};
+use crate::scan::{Asset, AssetKind};
+use crate::sniff::{Container, Sniffed};
+
+/// What sort of problem a deep check found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The data stops before the image or stream does.
+    Truncated,
+    /// Corrupt data the decoder complained about or gave up on.
+    Decode,
+    /// A video decoded to a different number of frames than it claims.
+    FrameCount,
+    /// The check couldn't run, so nothing is known either way.
+    NotChecked,
+}
+
+impl IssueKind {
+    /// Whether this is something wrong with the file, rather than the
+    /// check not running.
+    pub fn is_problem(self) -> bool {
+        self != IssueKind::NotChecked
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IssueKind::Truncated => "truncated",
+            IssueKind::Decode => "decode errors",
+            IssueKind::FrameCount => "frame count mismatch",
+            IssueKind::NotChecked => "not checked",
+        }
+    }
+}
+
+/// One problem found in a file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+impl Issue {
+    fn new(kind: IssueKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+}
+
+/// Decode all of `asset` and return what's wrong with it, if anything.
+/// Fails only when cancelled.
+pub fn check(asset: &Asset, cancel: &Cancel) -> Result<Vec<Issue>, Cancelled> {
+    cancel.check()?;
+    match asset.kind {
+        AssetKind::Image => Ok(check_image(&asset.path).into_iter().collect()),
+        AssetKind::Video => check_video(asset, cancel),
+    }
+}
+
+/// Decode an image at full size. JPEGs go through jpeg-decoder, which
+/// notices missing scan data where the `image` crate quietly fills it in.
+fn check_image(path: &Path) -> Option<Issue> {
+    let decoded = match crate::sniff::sniff(path) {
+        Ok(Sniffed::Known(Container::Jpeg)) => std::fs::File::open(path)
+            .map_err(Box::<dyn std::error::Error>::from)
+            .and_then(|file| {
+                jpeg_decoder::Decoder::new(BufReader::new(file)).decode()?;
+                Ok(())
+            }),
+        _ => image::ImageReader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(Box::<dyn std::error::Error>::from)
+            .and_then(|reader| {
+                reader.decode()?;
+                Ok(())
+            }),
+    };
+
+    let err = decoded.err()?;
+    Some(if ends_early(err.as_ref()) {
+        Issue::new(IssueKind::Truncated, "file ends before the image data does")
+    } else {
+        Issue::new(IssueKind::Decode, err.to_string())
+    })
+}
+
+/// Whether `err` comes down to running out of data.
+fn ends_early(err: &(dyn std::error::Error + 'static)) -> bool {
+    let eof = |io: &std::io::Error| io.kind() == std::io::ErrorKind::UnexpectedEof;
+    std::iter::successors(Some(err), |e| e.source()).any(|e| {
+        // `ImageError` skips over its own io error when asked for a source
+        match e.downcast_ref::<image::ImageError>() {
+            Some(image::ImageError::IoError(io)) => eof(io),
+            _ => e.downcast_ref::<std::io::Error>().is_some_and(eof),
+        }
+    })
+}
+
+/// Decode a video to nowhere with ffmpeg, collecting its complaints and
+/// counting the frames that come out.
+fn check_video(asset: &Asset, cancel: &Cancel) -> Result<Vec<Issue>, Cancelled> {
+    let output = cancel.output(
+        std::process::Command::new("ffmpeg")
+            .args(["-nostdin", "-v", "error", "-i"])
+            .arg(&asset.path)
+            .args(["-progress", "pipe:1", "-nostats", "-f", "null", "-"]),
+    );
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if crate::cancel::is_cancelled(&e) => return Err(Cancelled),
+        Err(e) => {
+            let missing = e
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|io| io.kind() == std::io::ErrorKind::NotFound);
+            let message = if missing {
+                String::from("ffmpeg not found — install with: brew install ffmpeg")
+            } else {
+                format!("cannot run ffmpeg: {e:#}")
+            };
+            return Ok(vec![Issue::new(IssueKind::NotChecked, message)]);
+        }
+    };
+
+    let mut issues = Vec::new();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let (short, errors): (Vec<&str>, Vec<&str>) = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .partition(|l| {
+            let l = l.to_lowercase();
+            l.contains("partial file") || l.contains("truncat") || l.contains("end of file")
+        });
+    if let Some(first) = short.first() {
+        issues.push(Issue::new(IssueKind::Truncated, *first));
+    }
+    match errors.as_slice() {
+        [] if !output.status.success() && short.is_empty() => issues.push(Issue::new(
+            IssueKind::Decode,
+            format!("ffmpeg failed (exit code {:?})", output.status.code()),
+        )),
+        [] => {}
+        [only] => issues.push(Issue::new(IssueKind::Decode, *only)),
+        [first, ..] => issues.push(Issue::new(
+            IssueKind::Decode,
+            format!("{} errors, the first: {first}", errors.len()),
+        )),
+    }
+
+    // `-progress` repeats its report as it goes; the last one has the total
+    let decoded = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .rev()
+        .find_map(|l| l.strip_prefix("frame="))
+        .and_then(|n| n.trim().parse::<u64>().ok());
+    if let (Some(decoded), Some(expected)) = (decoded.filter(|_| output.status.success()), asset.frames) {
+        // Edit lists and the odd dropped frame at the end are normal
+        let tolerance = (expected / 100).max(1);
+        if decoded.abs_diff(expected) > tolerance {
+            issues.push(Issue::new(
+                IssueKind::FrameCount,
+                format!("decoded {decoded} of {expected} frames"),
+            ));
+        }
+    }
+    Ok(issues)
+}
@@ -1,10 +1,16 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
-use image::GenericImageView;
+use anyhow::Result;
 use rayon::prelude::*;
 use serde::Serialize;
 
+use crate::cancel::Cancel;
+use crate::error::{DiscoverError, ProcessError};
+use crate::extract::{Candidate, Extractors, Job};
+use crate::qc::Issue;
+use crate::sniff::{Container, Sniffed};
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum AssetKind {
     Image,
@@ -23,6 +29,8 @@ impl std::fmt::Display for AssetKind {
 #[derive(Debug, Clone, Serialize)]
 pub struct Asset {
     pub filename: String,
+    #[serde(skip)]
+    pub path: PathBuf,
     pub kind: AssetKind,
     pub width: Option<u32>,
     pub height: Option<u32>,
@@ -31,8 +39,39 @@ pub struct Asset {
     pub color_space: Option<String>,
     pub duration: Option<f64>,
     pub codec: Option<String>,
+    /// Frames in a video, as the container declares or as its duration and
+    /// frame rate work out to.
+    #[serde(skip)]
+    pub frames: Option<u64>,
+    /// Hex SHA-256 of the file.
+    #[serde(skip)]
+    pub checksum: Option<String>,
+    /// Perceptual hash of an image's thumbnail, see `thumb::dhash`.
+    #[serde(skip)]
+    pub dhash: Option<u64>,
     #[serde(skip)]
     pub thumbnail_path: Option<PathBuf>,
+    #[serde(skip)]
+    pub filmstrip: Vec<FilmstripFrame>,
+    /// Why the file couldn't be read, for placeholders put in the proof
+    /// anyway.
+    #[serde(skip)]
+    pub error: Option<ProcessError>,
+    /// Things worth a look that didn't stop the file being read, such as
+    /// an extension that doesn't match the content.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// What a deep check found, or `None` when there wasn't one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qc: Option<Vec<Issue>>,
+}
+
+/// One frame of a video filmstrip.
+#[derive(Debug, Clone)]
+pub struct FilmstripFrame {
+    /// Position in the clip, in seconds.
+    pub time: f64,
+    pub path: PathBuf,
 }
 
 impl Asset {
@@ -46,32 +85,105 @@ impl Asset {
     pub fn human_size(&self) -> String {
         humansize::format_size(self.file_size, humansize::BINARY)
     }
+
+    /// What a deep check reported, nothing if it wasn't run.
+    pub fn issues(&self) -> &[Issue] {
+        self.qc.as_deref().unwrap_or_default()
+    }
+
+    /// Whether a deep check found something wrong with the file.
+    pub fn has_problems(&self) -> bool {
+        self.issues().iter().any(|i| i.kind.is_problem())
+    }
+
+    /// Whether a deep check was asked for but couldn't run, as when ffmpeg
+    /// is missing.
+    pub fn is_unchecked(&self) -> bool {
+        self.issues().iter().any(|i| !i.kind.is_problem())
+    }
+}
+
+/// Settings for `process_all` and `process_one`. Start from `default()`
+/// and change what you need, since fields are added over time.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ProcessOptions {
+    pub gen_thumbnails: bool,
+    pub auto_orient: bool,
+    /// Longest edge of generated thumbnails, in pixels.
+    pub thumb_size: u32,
+    /// JPEG quality for generated thumbnails (1-100).
+    pub thumb_quality: u8,
+    /// Poster frame timestamps in seconds, keyed by video path.
+    pub posters: HashMap<PathBuf, f64>,
+    /// Evenly spaced frames to extract per video (0 disables filmstrips).
+    pub filmstrip: u8,
+    /// Stops processing and kills ffmpeg/ffprobe when cancelled.
+    pub cancel: Cancel,
+    /// What reads each file's metadata and makes its thumbnail.
+    pub extractors: Extractors,
+    /// Decode every file in full afterwards and record problems in
+    /// `Asset::qc`.
+    pub deep_check: bool,
+    /// Hash every file for `Asset::checksum`. This reads all of each file,
+    /// so runs that only need headers leave it off.
+    pub checksums: bool,
 }
 
-fn classify(ext: &str) -> Option<AssetKind> {
-    match ext.to_ascii_lowercase().as_str() {
-        "jpg" | "jpeg" | "png" | "tiff" | "tif" | "webp" => Some(AssetKind::Image),
-        "mp4" | "mov" | "mxf" => Some(AssetKind::Video),
-        _ => None,
+impl Default for ProcessOptions {
+    /// Thumbnails for a four-column sheet, checksums, and nothing else
+    /// extra.
+    fn default() -> Self {
+        Self {
+            gen_thumbnails: true,
+            auto_orient: false,
+            thumb_size: crate::pdf::default_thumb_size(4),
+            thumb_quality: 90,
+            posters: HashMap::new(),
+            filmstrip: 0,
+            cancel: Cancel::default(),
+            extractors: Extractors::default(),
+            deep_check: false,
+            checksums: true,
+        }
     }
 }
 
-pub fn discover(dir: &Path) -> Result<Vec<(PathBuf, AssetKind)>> {
-    anyhow::ensure!(dir.is_dir(), "'{}' is not a directory", dir.display());
+/// Whether the built-in extractors pick up `path`, and as what.
+pub fn kind_of(path: &Path) -> Option<AssetKind> {
+    Extractors::default().kind_of(path)
+}
+
+/// Whether `path` has an extension the built-in extractors read. Unlike
+/// `kind_of` it doesn't open the file.
+pub fn has_supported_extension(path: &Path) -> bool {
+    Container::from_extension(&extension(path)).is_some_and(|c| Container::SUPPORTED.contains(&c))
+}
+
+/// Find the files under `dir` the built-in extractors handle.
+pub fn discover(dir: &Path) -> Result<Vec<(PathBuf, AssetKind)>, DiscoverError> {
+    discover_with(dir, &Extractors::default())
+}
+
+/// Find the files under `dir` that `extractors` claim, in natural filename
+/// order. Hidden files and folders are skipped.
+pub fn discover_with(dir: &Path, extractors: &Extractors) -> Result<Vec<(PathBuf, AssetKind)>, DiscoverError> {
+    if !dir.is_dir() {
+        return Err(DiscoverError::NotADirectory(dir.to_path_buf()));
+    }
 
     let mut assets: Vec<(PathBuf, AssetKind)> = walkdir::WalkDir::new(dir)
         .into_iter()
         .filter_entry(|e| {
             !e.file_name()
                 .to_str()
-                .map_or(false, |s| s.starts_with('.'))
+                .is_some_and(|s| s.starts_with('.'))
         })
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
         .filter_map(|e| {
             let path = e.into_path();
-            let ext = path.extension()?.to_str()?;
-            let kind = classify(ext)?;
+            let kind = extractors.kind_of(&path)?;
             Some((path, kind))
         })
         .collect();
@@ -83,28 +195,40 @@ pub fn discover(dir: &Path) -> Result<Vec<(PathBuf, AssetKind)>> {
         )
     });
 
-    anyhow::ensure!(
-        !assets.is_empty(),
-        "No supported assets found in '{}'",
-        dir.display()
-    );
-
+    if assets.is_empty() {
+        return Err(DiscoverError::NoAssets(dir.to_path_buf()));
+    }
     Ok(assets)
 }
 
+/// Per-file progress reported by `process_all`, from whichever worker
+/// thread handles the file. `index` is the position in the input slice.
+#[derive(Debug, Clone)]
+pub enum Progress {
+    Processing { index: usize },
+    Processed { index: usize, asset: Box<Asset> },
+    Failed { index: usize, error: ProcessError },
+}
+
 pub fn process_all(
     assets: &[(PathBuf, AssetKind)],
     thumb_dir: &Path,
-    gen_thumbnails: bool,
-    auto_orient: bool,
-) -> (Vec<Asset>, Vec<String>) {
-    eprintln!("Processing {} assets...", assets.len());
-
-    let results: Vec<Result<Asset>> = assets
+    opts: &ProcessOptions,
+    on_progress: impl Fn(Progress) + Sync,
+) -> (Vec<Asset>, Vec<ProcessError>) {
+    let results: Vec<Result<Asset, ProcessError>> = assets
         .par_iter()
         .enumerate()
-        .map(|(i, (path, kind))| {
-            process_one(path, *kind, thumb_dir, i, gen_thumbnails, auto_orient)
+        .map(|(index, (path, kind))| {
+            opts.cancel.check()?;
+            on_progress(Progress::Processing { index });
+            let result = process_one(path, *kind, thumb_dir, index, opts);
+            match &result {
+                Ok(asset) => on_progress(Progress::Processed { index, asset: Box::new(asset.clone()) }),
+                Err(ProcessError::Cancelled) => {}
+                Err(e) => on_progress(Progress::Failed { index, error: e.clone() }),
+            }
+            result
         })
         .collect();
 
@@ -114,12 +238,11 @@ pub fn process_all(
     for result in results {
         match result {
             Ok(asset) => processed.push(asset),
-            Err(e) => errors.push(format!("{:#}", e)),
+            Err(ProcessError::Cancelled) => {}
+            Err(e) => errors.push(e),
         }
     }
 
-    processed.sort_by(|a, b| natord::compare(&a.filename, &b.filename));
-
     (processed, errors)
 }
 
@@ -128,83 +251,189 @@ pub fn process_one(
     kind: AssetKind,
     thumb_dir: &Path,
     index: usize,
-    gen_thumbnails: bool,
-    auto_orient: bool,
-) -> Result<Asset> {
+    opts: &ProcessOptions,
+) -> Result<Asset, ProcessError> {
     let filename = path
         .file_name()
-        .context("no filename")?
+        .unwrap_or_default()
         .to_string_lossy()
         .to_string();
 
-    let file_size = std::fs::metadata(path)
-        .with_context(|| format!("cannot stat '{}'", path.display()))?
-        .len();
+    let io = |e: std::io::Error| ProcessError::Io { path: path.to_path_buf(), message: e.to_string() };
+    let file_size = std::fs::metadata(path).map_err(io)?.len();
 
-    let format = path
-        .extension()
-        .unwrap_or_default()
-        .to_str()
-        .unwrap_or("")
-        .to_uppercase();
+    // Catch placeholders and mislabelled files up front, rather than
+    // failing somewhere in a decoder. Discovery may have read the header
+    // already, but the file can have changed since, as it often has in
+    // watch mode
+    let sniffed = crate::sniff::sniff(path).map_err(io)?;
+    if sniffed == Sniffed::Empty {
+        return Err(ProcessError::Decode { path: path.to_path_buf(), message: "file is empty".into() });
+    }
+    let mut warnings = Vec::new();
+    if let Sniffed::Known(actual) = sniffed {
+        let ext = extension(path);
+        if !actual.extensions().contains(&ext.as_str()) {
+            warnings.push(format!("extension is .{ext} but the content is {}", actual.name()));
+        }
+    }
 
     let mut asset = Asset {
         filename,
+        path: path.to_path_buf(),
         kind,
         width: None,
         height: None,
         file_size,
-        format,
+        format: match sniffed {
+            Sniffed::Known(actual) => actual.name().to_string(),
+            _ => format_of(path),
+        },
         color_space: None,
         duration: None,
         codec: None,
+        frames: None,
+        checksum: None,
+        dhash: None,
         thumbnail_path: None,
+        filmstrip: Vec::new(),
+        error: None,
+        warnings,
+        qc: None,
     };
 
-    match kind {
-        AssetKind::Image => process_image(&mut asset, path, thumb_dir, index, gen_thumbnails, auto_orient)?,
-        AssetKind::Video => process_video(&mut asset, path, thumb_dir, index, gen_thumbnails),
+    if opts.checksums {
+        match crate::dupes::checksum(path, &opts.cancel) {
+            Ok(sum) => asset.checksum = Some(sum),
+            Err(e) if crate::cancel::is_cancelled(&e) => return Err(ProcessError::Cancelled),
+            // Only duplicates and change tracking miss out, so carry on
+            Err(e) => asset.warnings.push(format!("cannot checksum: {e:#}")),
+        }
+    }
+    let file = Candidate::with_sniffed(path, sniffed);
+    opts.extractors.extract(&file, &mut asset, &Job { thumb_dir, index, opts })?;
+    if opts.deep_check {
+        asset.qc = Some(crate::qc::check(&asset, &opts.cancel)?);
     }
-
     Ok(asset)
 }
 
-fn process_image(
-    asset: &mut Asset,
-    path: &Path,
-    thumb_dir: &Path,
-    index: usize,
-    gen_thumbnails: bool,
-    auto_orient: bool,
-) -> Result<()> {
-    if gen_thumbnails {
-        let img = image::open(path)
-            .with_context(|| format!("cannot decode '{}'", path.display()))?;
-        let img = if auto_orient {
-            apply_orientation(img, read_exif_orientation(path))
+/// Stand-in for a file that failed processing, listed in the proof as
+/// unreadable rather than dropped.
+pub fn placeholder(path: &Path, kind: AssetKind, error: ProcessError) -> Asset {
+    Asset {
+        filename: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        path: path.to_path_buf(),
+        kind,
+        width: None,
+        height: None,
+        file_size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        format: format_of(path),
+        color_space: None,
+        duration: None,
+        codec: None,
+        frames: None,
+        checksum: None,
+        dhash: None,
+        thumbnail_path: None,
+        filmstrip: Vec::new(),
+        error: Some(error),
+        warnings: Vec::new(),
+        qc: None,
+    }
+}
+
+#[cfg(test)]
+impl Asset {
+    /// A readable asset at `path` with nothing read from it yet.
+    pub(crate) fn stub(path: &Path, kind: AssetKind) -> Self {
+        Self {
+            filename: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            path: path.to_path_buf(),
+            kind,
+            width: None,
+            height: None,
+            file_size: 0,
+            format: format_of(path),
+            color_space: None,
+            duration: None,
+            codec: None,
+            frames: None,
+            checksum: None,
+            dhash: None,
+            thumbnail_path: None,
+            filmstrip: Vec::new(),
+            error: None,
+            warnings: Vec::new(),
+            qc: None,
+        }
+    }
+}
+
+/// The format the extension claims, for files whose content isn't known.
+fn format_of(path: &Path) -> String {
+    let ext = extension(path);
+    match Container::from_extension(&ext) {
+        Some(container) => container.name().to_string(),
+        None => ext.to_uppercase(),
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+/// The built-in image extractor.
+pub(crate) fn process_image(asset: &mut Asset, job: &Job) -> Result<(), ProcessError> {
+    let (path, opts) = (&asset.path.clone(), job.opts);
+    if opts.gen_thumbnails {
+        let decoded = crate::thumb::decode(path, opts.thumb_size)
+            .map_err(|e| ProcessError::image(path, e))?;
+        let orientation = if opts.auto_orient { read_exif_orientation(path) } else { 1 };
+        let (w, h) = if (5..=8).contains(&orientation) {
+            (decoded.height, decoded.width)
         } else {
-            img
+            (decoded.width, decoded.height)
         };
-        let (w, h) = img.dimensions();
         asset.width = Some(w);
         asset.height = Some(h);
-
-        let thumb = img.thumbnail(300, 300);
-        let thumb_path = thumb_dir.join(format!("{:04}.jpg", index));
-        thumb.save(&thumb_path)
-            .with_context(|| format!("cannot save thumbnail for '{}'", path.display()))?;
+        asset.color_space = decoded.icc_profile.as_deref().and_then(crate::color::describe);
+
+        let thumb = crate::thumb::resize(apply_orientation(decoded.image, orientation), opts.thumb_size);
+        asset.dhash = Some(crate::thumb::dhash(&thumb));
+        let thumb_path = job.thumb_path();
+        crate::thumb::save_jpeg(&thumb, &thumb_path, opts.thumb_quality).map_err(|e| {
+            ProcessError::Io { path: thumb_path.clone(), message: format!("cannot save thumbnail: {e:#}") }
+        })?;
         asset.thumbnail_path = Some(thumb_path);
     } else {
-        let (w, h) = image::image_dimensions(path)
-            .with_context(|| format!("cannot read dimensions of '{}'", path.display()))?;
-        asset.width = Some(w);
-        asset.height = Some(h);
+        let header = crate::thumb::probe(path).map_err(|e| ProcessError::image(path, e))?;
+        asset.width = Some(header.width);
+        asset.height = Some(header.height);
+        asset.color_space = header.icc_profile.as_deref().and_then(crate::color::describe);
     }
 
-    read_exif(asset, path);
+    if asset.color_space.is_none() {
+        read_exif(asset, path);
+    }
     Ok(())
 }
 
+/// Decode an sRGB preview of an image that fits a `size`x`size` box, for
+/// places that need more than the contact sheet thumbnail.
+pub(crate) fn preview(path: &Path, size: u32, auto_orient: bool) -> Result<image::DynamicImage> {
+    let decoded = crate::thumb::decode(path, size)?;
+    let orientation = if auto_orient { read_exif_orientation(path) } else { 1 };
+    Ok(crate::thumb::resize(apply_orientation(decoded.image, orientation), size))
+}
+
 fn read_exif_orientation(path: &Path) -> u32 {
     let Ok(file) = std::fs::File::open(path) else { return 1 };
     let mut reader = std::io::BufReader::new(file);
@@ -229,6 +458,7 @@ fn apply_orientation(img: image::DynamicImage, orientation: u32) -> image::Dynam
     }
 }
 
+/// Fallback for files without an embedded ICC profile.
 fn read_exif(asset: &mut Asset, path: &Path) {
     let Ok(file) = std::fs::File::open(path) else { return };
     let mut reader = std::io::BufReader::new(file);
@@ -239,48 +469,202 @@ fn read_exif(asset: &mut Asset, path: &Path) {
     }
 }
 
-fn process_video(
-    asset: &mut Asset,
+/// The built-in video extractor.
+pub(crate) fn process_video(asset: &mut Asset, job: &Job) -> Result<(), ProcessError> {
+    let (path, opts) = (&asset.path.clone(), job.opts);
+    let (thumb_dir, index) = (job.thumb_dir, job.index);
+    let json = probe_video(path, opts)?;
+    if let Some(streams) = json["streams"].as_array() {
+        for stream in streams {
+            if stream["codec_type"].as_str() == Some("video") {
+                asset.width = stream["width"].as_u64().map(|v| v as u32);
+                asset.height = stream["height"].as_u64().map(|v| v as u32);
+                asset.codec = stream["codec_name"].as_str().map(String::from);
+                asset.frames = frame_count(stream);
+                break;
+            }
+        }
+    }
+    if let Some(duration) = json["format"]["duration"].as_str() {
+        asset.duration = duration.parse::<f64>().ok();
+    }
+
+    if opts.gen_thumbnails {
+        let thumb_path = job.thumb_path();
+        let candidates = match opts.posters.get(path) {
+            Some(&at) => vec![at],
+            None => poster_candidates(asset.duration),
+        };
+
+        if let Some(frame) = pick_poster(path, &candidates, thumb_dir, index, opts) {
+            if std::fs::rename(&frame, &thumb_path).is_ok() {
+                asset.thumbnail_path = Some(thumb_path);
+            }
+        }
+
+        if let Some(duration) = asset.duration.filter(|_| opts.filmstrip > 0) {
+            asset.filmstrip = extract_filmstrip(path, duration, thumb_dir, index, opts);
+        }
+    }
+
+    // Frame extraction treats a killed ffmpeg like any other failure, so
+    // check here that the asset isn't just missing frames because of it.
+    Ok(opts.cancel.check()?)
+}
+
+/// Frames in a video stream from its ffprobe report. Streams without an
+/// `nb_frames` get an estimate from their duration and frame rate instead.
+fn frame_count(stream: &serde_json::Value) -> Option<u64> {
+    if let Some(frames) = stream["nb_frames"].as_str().and_then(|n| n.parse().ok()) {
+        return Some(frames);
+    }
+    let duration: f64 = stream["duration"].as_str()?.parse().ok()?;
+    let (num, den) = stream["avg_frame_rate"].as_str()?.split_once('/')?;
+    let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+    (den > 0.0 && num > 0.0).then(|| (duration * num / den).round() as u64)
+}
+
+/// Run ffprobe on `path` and parse its JSON report.
+fn probe_video(path: &Path, opts: &ProcessOptions) -> Result<serde_json::Value, ProcessError> {
+    let output = opts.cancel.output(
+        std::process::Command::new("ffprobe")
+            .args(["-v", "error", "-print_format", "json", "-show_streams", "-show_format"])
+            .arg(path),
+    );
+    let failed = |message: String| ProcessError::ProbeFailed { path: path.to_path_buf(), message };
+
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if crate::cancel::is_cancelled(&e) => return Err(ProcessError::Cancelled),
+        Err(e) => {
+            let missing = e
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|io| io.kind() == std::io::ErrorKind::NotFound);
+            return Err(if missing {
+                ProcessError::ProbeMissing { path: path.to_path_buf() }
+            } else {
+                failed(format!("{e:#}"))
+            });
+        }
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failed(match stderr.trim() {
+            "" => format!("exit code {:?}", output.status.code()),
+            text => text.lines().last().unwrap_or(text).to_string(),
+        }));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| failed(format!("unreadable report: {e}")))
+}
+
+/// Extract `opts.filmstrip` frames from the middle of evenly sized slices
+/// of the clip. Frames that fail to extract are left out.
+fn extract_filmstrip(
     path: &Path,
+    duration: f64,
     thumb_dir: &Path,
     index: usize,
-    gen_thumbnails: bool,
-) {
-    if let Ok(output) = std::process::Command::new("ffprobe")
-        .args(["-v", "quiet", "-print_format", "json", "-show_streams", "-show_format"])
-        .arg(path)
-        .output()
-    {
-        if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&output.stdout) {
-            if let Some(streams) = json["streams"].as_array() {
-                for stream in streams {
-                    if stream["codec_type"].as_str() == Some("video") {
-                        asset.width = stream["width"].as_u64().map(|v| v as u32);
-                        asset.height = stream["height"].as_u64().map(|v| v as u32);
-                        asset.codec = stream["codec_name"].as_str().map(String::from);
-                        break;
-                    }
-                }
-            }
-            if let Some(duration) = json["format"]["duration"].as_str() {
-                asset.duration = duration.parse::<f64>().ok();
+    opts: &ProcessOptions,
+) -> Vec<FilmstripFrame> {
+    let count = opts.filmstrip as usize;
+    (0..count)
+        .filter_map(|n| {
+            let time = duration * (n as f64 + 0.5) / count as f64;
+            let frame = thumb_dir.join(format!("{:04}-f{:02}.jpg", index, n));
+            extract_frame(path, time, &frame, opts).then_some(FilmstripFrame { time, path: frame })
+        })
+        .collect()
+}
+
+// Fractions of the duration to try, in order, when looking for a poster
+// frame. The first one is past most slates and fade-ins.
+const POSTER_POSITIONS: &[f64] = &[0.1, 0.25, 0.5, 0.75];
+
+fn poster_candidates(duration: Option<f64>) -> Vec<f64> {
+    match duration {
+        Some(d) if d >= 1.0 => POSTER_POSITIONS.iter().map(|p| d * p).collect(),
+        Some(d) if d > 0.0 => vec![d / 2.0],
+        _ => vec![1.0],
+    }
+}
+
+/// Grab a frame at each candidate timestamp until one isn't black or a
+/// flat colour, keeping the most detailed frame seen otherwise. Clips too
+/// short to seek into fall back to their very first frame.
+fn pick_poster(
+    path: &Path,
+    candidates: &[f64],
+    thumb_dir: &Path,
+    index: usize,
+    opts: &ProcessOptions,
+) -> Option<PathBuf> {
+    let mut best: Option<(PathBuf, f64)> = None;
+
+    for (n, &at) in candidates.iter().enumerate() {
+        if opts.cancel.is_cancelled() {
+            break;
+        }
+        let frame = thumb_dir.join(format!("{:04}-poster{}.jpg", index, n));
+        if !extract_frame(path, at, &frame, opts) {
+            continue;
+        }
+        let Some((mean, stddev)) = luma_stats(&frame) else { continue };
+
+        let usable = mean > 16.0 && stddev > 8.0;
+        if usable || best.as_ref().is_none_or(|(_, s)| stddev > *s) {
+            if let Some((previous, _)) = best.replace((frame, stddev)) {
+                let _ = std::fs::remove_file(previous);
             }
+        } else {
+            let _ = std::fs::remove_file(&frame);
+        }
+
+        if usable {
+            break;
         }
     }
 
-    if gen_thumbnails {
-        let thumb_path = thumb_dir.join(format!("{:04}.jpg", index));
-        let status = std::process::Command::new("ffmpeg")
-            .args(["-y", "-ss", "1", "-i"])
+    if best.is_none() {
+        let frame = thumb_dir.join(format!("{:04}-poster.jpg", index));
+        if extract_frame(path, 0.0, &frame, opts) {
+            return Some(frame);
+        }
+    }
+
+    best.map(|(frame, _)| frame)
+}
+
+fn extract_frame(path: &Path, at: f64, out: &Path, opts: &ProcessOptions) -> bool {
+    let scale = format!(
+        "scale={0}:{0}:force_original_aspect_ratio=decrease:flags=lanczos",
+        opts.thumb_size
+    );
+    let status = opts.cancel.status(
+        std::process::Command::new("ffmpeg")
+            .args(["-y", "-ss", &format!("{:.3}", at), "-i"])
             .arg(path)
-            .args(["-frames:v", "1", "-vf", "scale=300:-1"])
-            .arg(&thumb_path)
+            .args(["-frames:v", "1", "-vf", &scale, "-q:v"])
+            .arg(crate::thumb::ffmpeg_qscale(opts.thumb_quality).to_string())
+            .arg(out)
             .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status();
+            .stderr(std::process::Stdio::null()),
+    );
 
-        if status.map_or(false, |s| s.success()) {
-            asset.thumbnail_path = Some(thumb_path);
-        }
+    // ffmpeg exits successfully without writing anything when seeking past
+    // the end of a clip.
+    status.is_ok_and(|s| s.success()) && out.is_file()
+}
+
+/// Mean and standard deviation of a frame's luma, on a 0-255 scale.
+fn luma_stats(frame: &Path) -> Option<(f64, f64)> {
+    let luma = image::open(frame).ok()?.to_luma8();
+    let count = luma.len() as f64;
+    if count == 0.0 {
+        return None;
     }
+
+    let mean = luma.iter().map(|&v| v as f64).sum::<f64>() / count;
+    let variance = luma.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / count;
+    Some((mean, variance.sqrt()))
 }
@@ -0,0 +1,201 @@
+//! Recognise file formats from their first bytes rather than trusting the
+//! extension.
+
+use std::io::Read;
+use std::path::Path;
+
+use crate::scan::AssetKind;
+
+/// Formats recognised by their magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Jpeg,
+    Png,
+    Tiff,
+    Webp,
+    Gif,
+    Heif,
+    Mp4,
+    Mov,
+    Mxf,
+}
+
+impl Container {
+    /// Formats the built-in extractors read.
+    pub const SUPPORTED: &[Container] = &[
+        Container::Jpeg,
+        Container::Png,
+        Container::Tiff,
+        Container::Webp,
+        Container::Mp4,
+        Container::Mov,
+        Container::Mxf,
+    ];
+
+    /// As shown in the manifest `format` column.
+    pub fn name(self) -> &'static str {
+        match self {
+            Container::Jpeg => "JPEG",
+            Container::Png => "PNG",
+            Container::Tiff => "TIFF",
+            Container::Webp => "WEBP",
+            Container::Gif => "GIF",
+            Container::Heif => "HEIF",
+            Container::Mp4 => "MP4",
+            Container::Mov => "MOV",
+            Container::Mxf => "MXF",
+        }
+    }
+
+    pub fn kind(self) -> AssetKind {
+        match self {
+            Container::Mp4 | Container::Mov | Container::Mxf => AssetKind::Video,
+            _ => AssetKind::Image,
+        }
+    }
+
+    /// Extensions files of this format normally have, lowercase.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Container::Jpeg => &["jpg", "jpeg"],
+            Container::Png => &["png"],
+            Container::Tiff => &["tif", "tiff"],
+            Container::Webp => &["webp"],
+            Container::Gif => &["gif"],
+            Container::Heif => &["heic", "heif"],
+            Container::Mp4 => &["mp4", "m4v"],
+            Container::Mov => &["mov"],
+            Container::Mxf => &["mxf"],
+        }
+    }
+
+    /// The format a lowercase extension claims.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        const ALL: &[Container] = &[
+            Container::Jpeg,
+            Container::Png,
+            Container::Tiff,
+            Container::Webp,
+            Container::Gif,
+            Container::Heif,
+            Container::Mp4,
+            Container::Mov,
+            Container::Mxf,
+        ];
+        ALL.iter().copied().find(|c| c.extensions().contains(&ext))
+    }
+}
+
+/// What a file's first bytes say it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sniffed {
+    /// Zero bytes long, as with placeholders from a sync that never finished.
+    Empty,
+    Known(Container),
+    /// Not a format recognised here, or unreadable.
+    Unknown,
+}
+
+/// Read the start of `path` and recognise its format.
+pub fn sniff(path: &Path) -> std::io::Result<Sniffed> {
+    let mut head = Vec::with_capacity(16);
+    std::fs::File::open(path)?.take(16).read_to_end(&mut head)?;
+    Ok(detect(&head))
+}
+
+fn detect(head: &[u8]) -> Sniffed {
+    if head.is_empty() {
+        return Sniffed::Empty;
+    }
+    let at = |offset: usize, magic: &[u8]| head.get(offset..offset + magic.len()) == Some(magic);
+
+    let container = if at(0, &[0xFF, 0xD8, 0xFF]) {
+        Container::Jpeg
+    } else if at(0, b"\x89PNG\r\n\x1a\n") {
+        Container::Png
+    } else if at(0, b"II*\0") || at(0, b"MM\0*") || at(0, b"II+\0") || at(0, b"MM\0+") {
+        Container::Tiff
+    } else if at(0, b"RIFF") && at(8, b"WEBP") {
+        Container::Webp
+    } else if at(0, b"GIF87a") || at(0, b"GIF89a") {
+        Container::Gif
+    } else if at(4, b"ftyp") {
+        // ISO base media files name their flavour in the major brand
+        match head.get(8..12) {
+            Some(b"qt  ") => Container::Mov,
+            Some(b"heic" | b"heix" | b"hevc" | b"mif1" | b"msf1" | b"avif") => Container::Heif,
+            _ => Container::Mp4,
+        }
+    } else if at(4, b"moov") || at(4, b"mdat") || at(4, b"wide") || at(4, b"free") {
+        // QuickTime files from before `ftyp` existed
+        Container::Mov
+    } else if at(0, &[0x06, 0x0E, 0x2B, 0x34, 0x02, 0x05, 0x01, 0x01]) {
+        // SMPTE universal label of an MXF partition pack
+        Container::Mxf
+    } else {
+        return Sniffed::Unknown;
+    };
+    Sniffed::Known(container)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An ISO base media header with `brand` as the major brand.
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut head = vec![0, 0, 0, 0x18];
+        head.extend_from_slice(b"ftyp");
+        head.extend_from_slice(brand);
+        head.extend_from_slice(&[0, 0, 0, 0]);
+        head
+    }
+
+    #[test]
+    fn ftyp_brands() {
+        assert_eq!(detect(&ftyp(b"qt  ")), Sniffed::Known(Container::Mov));
+        assert_eq!(detect(&ftyp(b"isom")), Sniffed::Known(Container::Mp4));
+        assert_eq!(detect(&ftyp(b"mp42")), Sniffed::Known(Container::Mp4));
+        for brand in [b"heic", b"heix", b"hevc", b"mif1", b"msf1", b"avif"] {
+            assert_eq!(detect(&ftyp(brand)), Sniffed::Known(Container::Heif));
+        }
+    }
+
+    #[test]
+    fn quicktime_before_ftyp() {
+        for atom in [b"moov", b"mdat", b"wide", b"free"] {
+            let mut head = vec![0, 0, 0, 8];
+            head.extend_from_slice(atom);
+            assert_eq!(detect(&head), Sniffed::Known(Container::Mov));
+        }
+    }
+
+    #[test]
+    fn tiff_and_bigtiff() {
+        for head in [b"II*\0", b"MM\0*", b"II+\0", b"MM\0+"] {
+            assert_eq!(detect(head), Sniffed::Known(Container::Tiff));
+        }
+        assert_eq!(detect(b"II\0*"), Sniffed::Unknown);
+    }
+
+    #[test]
+    fn empty_and_short_headers() {
+        assert_eq!(detect(b""), Sniffed::Empty);
+        // Too short for the magic they start
+        assert_eq!(detect(&[0xFF, 0xD8]), Sniffed::Unknown);
+        assert_eq!(detect(b"\x89PNG"), Sniffed::Unknown);
+        assert_eq!(detect(b"RIFF\0\0\0\0WE"), Sniffed::Unknown);
+        assert_eq!(detect(b"\0\0\0\x18ftyp"), Sniffed::Known(Container::Mp4));
+        assert_eq!(detect(b"\0\0\0\x18fty"), Sniffed::Unknown);
+    }
+
+    #[test]
+    fn other_formats() {
+        assert_eq!(detect(&[0xFF, 0xD8, 0xFF, 0xE0]), Sniffed::Known(Container::Jpeg));
+        assert_eq!(detect(b"\x89PNG\r\n\x1a\n"), Sniffed::Known(Container::Png));
+        assert_eq!(detect(b"RIFF\0\0\0\0WEBPVP8 "), Sniffed::Known(Container::Webp));
+        assert_eq!(detect(b"GIF89a"), Sniffed::Known(Container::Gif));
+        assert_eq!(detect(&[0x06, 0x0E, 0x2B, 0x34, 0x02, 0x05, 0x01, 0x01]), Sniffed::Known(Container::Mxf));
+        assert_eq!(detect(b"%PDF-1.7"), Sniffed::Unknown);
+    }
+}
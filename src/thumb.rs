@@ -0,0 +1,228 @@
+use std::io::{BufReader, BufWriter, Cursor};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageDecoder, ImageFormat, ImageReader};
+
+/// A decoded sRGB preview together with the dimensions and embedded ICC
+/// profile of the original file.
+pub struct Decoded {
+    pub image: DynamicImage,
+    pub width: u32,
+    pub height: u32,
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+/// What can be learned from a file header without decoding pixels.
+pub struct Header {
+    pub width: u32,
+    pub height: u32,
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+/// Decode `path` at the smallest size that still covers a `target`x`target`
+/// box. JPEGs use an embedded EXIF preview when one is large enough, and
+/// otherwise decode directly at 1/2, 1/4 or 1/8 scale in the DCT domain.
+/// Everything else falls back to a full decode. Pixels are converted from
+/// the embedded ICC profile to sRGB.
+pub fn decode(path: &Path, target: u32) -> Result<Decoded> {
+    if sniff_format(path)? == Some(ImageFormat::Jpeg) {
+        return decode_jpeg(path, target);
+    }
+
+    let mut decoder = open_decoder(path)?;
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let image = DynamicImage::from_decoder(decoder)
+        .with_context(|| format!("cannot decode '{}'", path.display()))?;
+    let (width, height) = image.dimensions();
+    let image = match icc_profile {
+        Some(ref icc) => crate::color::to_srgb(image, icc)
+            .with_context(|| format!("cannot convert '{}' to sRGB", path.display()))?,
+        None => image,
+    };
+
+    Ok(Decoded { image, width, height, icc_profile })
+}
+
+/// Read image dimensions and ICC profile from the file header.
+pub fn probe(path: &Path) -> Result<Header> {
+    if sniff_format(path)? == Some(ImageFormat::Jpeg) {
+        let mut decoder = jpeg_decoder(path)?;
+        decoder
+            .read_info()
+            .with_context(|| format!("cannot read JPEG header of '{}'", path.display()))?;
+        let info = decoder.info().context("missing JPEG frame header")?;
+        return Ok(Header {
+            width: info.width as u32,
+            height: info.height as u32,
+            icc_profile: decoder.icc_profile(),
+        });
+    }
+
+    let mut decoder = open_decoder(path)?;
+    let (width, height) = decoder.dimensions();
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    Ok(Header { width, height, icc_profile })
+}
+
+/// Fit `image` inside a `size`x`size` box with Lanczos resampling. Images
+/// that already fit are returned unchanged rather than upscaled.
+pub fn resize(image: DynamicImage, size: u32) -> DynamicImage {
+    let (w, h) = image.dimensions();
+    if w <= size && h <= size {
+        return image;
+    }
+    image.resize(size, size, FilterType::Lanczos3)
+}
+
+/// 64-bit difference hash of `image`: whether each pixel of a 9x8 greyscale
+/// version is brighter than its right-hand neighbour. Copies of a picture at
+/// other sizes or qualities hash to the same or nearly the same bits.
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = hash << 1 | brighter as u64;
+        }
+    }
+    hash
+}
+
+/// Write `image` as an 8-bit RGB JPEG at the given quality (1-100).
+pub fn save_jpeg(image: &DynamicImage, path: &Path, quality: u8) -> Result<()> {
+    let file = std::fs::File::create(path)?;
+    let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+    JpegEncoder::new_with_quality(BufWriter::new(file), quality).encode_image(&rgb)?;
+    Ok(())
+}
+
+/// Shrink the image at `path` to fit a `size`x`size` box and return it as a
+/// `data:image/jpeg;base64,…` URI.
+pub fn data_uri(path: &Path, size: u32) -> Result<String> {
+    use base64::Engine;
+    let image = resize(image::open(path)?, size);
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, 75).encode_image(&DynamicImage::ImageRgb8(image.to_rgb8()))?;
+    let data = base64::engine::general_purpose::STANDARD.encode(jpeg);
+    Ok(format!("data:image/jpeg;base64,{data}"))
+}
+
+/// Map a 1-100 JPEG quality onto ffmpeg's `-q:v` scale (2 best, 31 worst).
+pub fn ffmpeg_qscale(quality: u8) -> u8 {
+    let quality = quality.clamp(1, 100) as u32;
+    (2 + (100 - quality) * 29 / 99) as u8
+}
+
+fn sniff_format(path: &Path) -> Result<Option<ImageFormat>> {
+    let reader = ImageReader::open(path)
+        .with_context(|| format!("cannot open '{}'", path.display()))?
+        .with_guessed_format()
+        .with_context(|| format!("cannot read '{}'", path.display()))?;
+    Ok(reader.format())
+}
+
+fn open_decoder(path: &Path) -> Result<impl ImageDecoder> {
+    ImageReader::open(path)
+        .with_context(|| format!("cannot open '{}'", path.display()))?
+        .with_guessed_format()
+        .with_context(|| format!("cannot read '{}'", path.display()))?
+        .into_decoder()
+        .with_context(|| format!("cannot read dimensions of '{}'", path.display()))
+}
+
+fn jpeg_decoder(path: &Path) -> Result<jpeg_decoder::Decoder<BufReader<std::fs::File>>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("cannot open '{}'", path.display()))?;
+    Ok(jpeg_decoder::Decoder::new(BufReader::new(file)))
+}
+
+fn decode_jpeg(path: &Path, target: u32) -> Result<Decoded> {
+    let mut decoder = jpeg_decoder(path)?;
+    decoder
+        .read_info()
+        .with_context(|| format!("cannot read JPEG header of '{}'", path.display()))?;
+    let info = decoder.info().context("missing JPEG frame header")?;
+    let (width, height) = (info.width as u32, info.height as u32);
+    let icc_profile = decoder.icc_profile();
+    let to_srgb = |image: DynamicImage| match icc_profile {
+        Some(ref icc) => crate::color::to_srgb(image, icc)
+            .with_context(|| format!("cannot convert '{}' to sRGB", path.display())),
+        None => Ok(image),
+    };
+
+    if let Some(image) = embedded_preview(decoder.exif_data(), width, height, target) {
+        let image = to_srgb(image)?;
+        return Ok(Decoded { image, width, height, icc_profile });
+    }
+
+    let side = target.min(u16::MAX as u32) as u16;
+    decoder
+        .scale(side, side)
+        .with_context(|| format!("cannot scale '{}'", path.display()))?;
+    let pixels = decoder
+        .decode()
+        .with_context(|| format!("cannot decode '{}'", path.display()))?;
+    let info = decoder.info().context("missing JPEG frame header")?;
+    let (w, h) = (info.width as u32, info.height as u32);
+    let image = match info.pixel_format {
+        jpeg_decoder::PixelFormat::CMYK32 => {
+            crate::color::cmyk_to_srgb(&pixels, w, h, icc_profile.as_deref())
+                .with_context(|| format!("cannot convert '{}' to sRGB", path.display()))?
+        }
+        format => to_srgb(
+            to_dynamic(pixels, w, h, format)
+                .with_context(|| format!("unexpected pixel buffer size in '{}'", path.display()))?,
+        )?,
+    };
+
+    Ok(Decoded { image, width, height, icc_profile })
+}
+
+/// Use the EXIF IFD1 thumbnail if it covers the target box and has the same
+/// aspect ratio as the main image (some cameras letterbox their previews).
+fn embedded_preview(exif: Option<&[u8]>, width: u32, height: u32, target: u32) -> Option<DynamicImage> {
+    let exif = exif::Reader::new().read_raw(exif?.to_vec()).ok()?;
+    let offset = exif
+        .get_field(exif::Tag::JPEGInterchangeFormat, exif::In::THUMBNAIL)?
+        .value
+        .get_uint(0)? as usize;
+    let len = exif
+        .get_field(exif::Tag::JPEGInterchangeFormatLength, exif::In::THUMBNAIL)?
+        .value
+        .get_uint(0)? as usize;
+    let bytes = exif.buf().get(offset..offset.checked_add(len)?)?;
+
+    let preview = image::load(Cursor::new(bytes), ImageFormat::Jpeg).ok()?;
+    let (pw, ph) = preview.dimensions();
+    if pw.max(ph) < target {
+        return None;
+    }
+
+    let main_ratio = width as f64 / height as f64;
+    let preview_ratio = pw as f64 / ph as f64;
+    let same_orientation = (pw >= ph) == (width >= height);
+    if !same_orientation || (main_ratio - preview_ratio).abs() / main_ratio > 0.01 {
+        return None;
+    }
+
+    Some(preview)
+}
+
+fn to_dynamic(pixels: Vec<u8>, w: u32, h: u32, format: jpeg_decoder::PixelFormat) -> Option<DynamicImage> {
+    match format {
+        jpeg_decoder::PixelFormat::L8 => image::GrayImage::from_raw(w, h, pixels).map(DynamicImage::ImageLuma8),
+        jpeg_decoder::PixelFormat::L16 => {
+            let samples = pixels
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            image::ImageBuffer::from_raw(w, h, samples).map(DynamicImage::ImageLuma16)
+        }
+        jpeg_decoder::PixelFormat::RGB24 => image::RgbImage::from_raw(w, h, pixels).map(DynamicImage::ImageRgb8),
+        jpeg_decoder::PixelFormat::CMYK32 => None,
+    }
+}
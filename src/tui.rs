@@ -1,27 +1,59 @@
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    MouseEventKind,
+};
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, Paragraph};
+use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Wrap};
 use ratatui::Frame;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
+use rayon::prelude::*;
+
+use proof::cancel;
+use proof::changes::{self, Previous};
+use proof::config;
+use proof::deliveries::{self, Delivery};
+use proof::error::{ErrorKind, ProcessError};
+use proof::html;
+use proof::manifest::Manifest;
+use proof::pdf;
+use proof::scan;
+use proof::{find_duplicates, output_for, write_package, DuplicateMatch, OutputFields, OutputTemplate, PackageContents};
+
 use crate::cli::Cli;
-use crate::pdf;
-use crate::scan;
+use crate::graphics::{self, Protocol};
+use crate::watch;
 
 // ── Messages from background thread ────────────────────────
 
 enum Msg {
-    AssetFound { filename: String, kind: String },
+    AssetFound { path: PathBuf, kind: scan::AssetKind },
     ScanDone { total: usize },
     Processing { index: usize },
-    Processed { index: usize },
-    Failed { index: usize, error: String },
+    Processed { index: usize, asset: Box<scan::Asset> },
+    Failed { index: usize, error: ProcessError },
+    Ready { thumbs: tempfile::TempDir },
+    /// Watch mode started another pass; the file list is rebuilt from here.
+    Rescan,
     Rendering,
-    Done { output: String, total: usize },
+    /// Watch mode found nothing that changes the proof.
+    Unchanged,
+    Done {
+        output: String,
+        revision: u32,
+        total: usize,
+        /// What `--package` wrote.
+        package: Option<String>,
+    },
+    Cancelled,
+    /// Shown in the footer without stopping anything.
+    Warning(String),
     Error(String),
 }
 
@@ -31,8 +63,12 @@ enum Msg {
 enum Phase {
     Scanning,
     Processing,
+    Review,
     Rendering,
+    Cancelling,
     Complete,
+    /// Rendered, waiting for the folder to change again.
+    Watching,
     Failed,
 }
 
@@ -41,47 +77,228 @@ enum FileStatus {
     Pending,
     Processing,
     Done,
-    Failed(String),
+    Failed(ProcessError),
 }
 
 #[derive(Clone)]
 struct FileEntry {
+    /// Position in discovery order, used by the pipeline to refer to it.
+    id: usize,
+    path: PathBuf,
+    /// Path relative to the input directory, matched by search.
+    rel_path: String,
     filename: String,
-    kind: String,
+    kind: scan::AssetKind,
     status: FileStatus,
+    included: bool,
+    asset: Option<scan::Asset>,
+    duplicate: Option<Duplicate>,
+}
+
+/// The other files that are the same picture as an entry.
+#[derive(Clone)]
+struct Duplicate {
+    matched: DuplicateMatch,
+    others: Vec<String>,
+    /// Left off the contact sheet by `--skip-duplicates`.
+    skipped: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Client,
+    Title,
+    Date,
+}
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::Client => "Client",
+            Field::Title => "Title",
+            Field::Date => "Date",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Field::Client => Field::Title,
+            Field::Title => Field::Date,
+            Field::Date => Field::Client,
+        }
+    }
+}
+
+struct Edit {
+    field: Field,
+    buffer: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum KindFilter {
+    All,
+    Image,
+    Video,
+}
+
+impl KindFilter {
+    fn next(self) -> Self {
+        match self {
+            KindFilter::All => KindFilter::Image,
+            KindFilter::Image => KindFilter::Video,
+            KindFilter::Video => KindFilter::All,
+        }
+    }
+
+    fn matches(self, entry: &FileEntry) -> bool {
+        match self {
+            KindFilter::All => true,
+            KindFilter::Image => entry.kind == scan::AssetKind::Image,
+            KindFilter::Video => entry.kind == scan::AssetKind::Video,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StatusFilter {
+    All,
+    Ok,
+    /// Failed files, optionally of one kind only.
+    Failed(Option<ErrorKind>),
+    /// Files the deep check found problems in.
+    Qc,
+    Duplicates,
+    Excluded,
+}
+
+impl StatusFilter {
+    fn label(self) -> String {
+        match self {
+            StatusFilter::All => "all".into(),
+            StatusFilter::Ok => "ok".into(),
+            StatusFilter::Failed(None) => "failed".into(),
+            StatusFilter::Failed(Some(kind)) => format!("failed: {}", kind.label()),
+            StatusFilter::Qc => "qc issues".into(),
+            StatusFilter::Duplicates => "duplicates".into(),
+            StatusFilter::Excluded => "excluded".into(),
+        }
+    }
+
+    /// The filter after this one in `filters`, starting over at the end.
+    fn next(self, filters: &[StatusFilter]) -> Self {
+        filters
+            .iter()
+            .skip_while(|&&f| f != self)
+            .nth(1)
+            .copied()
+            .unwrap_or(StatusFilter::All)
+    }
+
+    fn matches(self, entry: &FileEntry) -> bool {
+        match (self, &entry.status) {
+            (StatusFilter::All, _) => true,
+            (StatusFilter::Ok, FileStatus::Done) => entry.included,
+            (StatusFilter::Failed(None), FileStatus::Failed(_)) => true,
+            (StatusFilter::Failed(Some(kind)), FileStatus::Failed(err)) => err.kind() == kind,
+            (StatusFilter::Qc, _) => entry.asset.as_ref().is_some_and(scan::Asset::has_problems),
+            (StatusFilter::Duplicates, _) => entry.duplicate.is_some(),
+            (StatusFilter::Excluded, _) => !entry.included,
+            _ => false,
+        }
+    }
+}
+
+/// Failures are coloured by kind, so a batch of missing-ffprobe errors
+/// stands out from a corrupt file or two.
+fn failure_color(kind: ErrorKind) -> Color {
+    match kind {
+        ErrorKind::Decode | ErrorKind::Cancelled => Color::Red,
+        ErrorKind::Unsupported => Color::Magenta,
+        ErrorKind::Io => Color::LightRed,
+        ErrorKind::ProbeMissing | ErrorKind::ProbeFailed => Color::LightMagenta,
+    }
 }
 
 struct App {
     phase: Phase,
     files: Vec<FileEntry>,
-    scroll: usize,
+    /// Index into `files`, not into the filtered list.
+    selected: usize,
+    /// Rows in the file list, for page up/down.
+    page: usize,
+    query: String,
+    searching: bool,
+    kind_filter: KindFilter,
+    status_filter: StatusFilter,
+    details: bool,
     tick: u64,
     total_found: usize,
     processed_count: usize,
     failed_count: usize,
+    started: Option<Instant>,
+    workers: usize,
     client: String,
+    title: Option<String>,
     date: String,
     columns: u8,
+    /// `id` of the cover image entry.
+    hero: Option<usize>,
+    editing: Option<Edit>,
+    /// Cover fields changed with `e`, which are the only ones saved.
+    edited: Vec<Field>,
+    reordered: bool,
+    /// Keeps generated thumbnails alive until rendering is done.
+    thumbs: Option<tempfile::TempDir>,
+    protocol: Protocol,
+    /// Decoded thumbnail of the selected file, keyed by its path.
+    preview: Option<(PathBuf, image::DynamicImage)>,
+    /// Selected file to pick again when watch mode rebuilds the list.
+    reselect: Option<PathBuf>,
+    rendered_at: Option<String>,
     output_path: String,
+    /// Delivery number of the last render.
+    revision: u32,
+    /// The ZIP written with `--package`, and what went in it.
+    package: Option<String>,
     error_msg: Option<String>,
 }
 
 const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
 impl App {
-    fn new(client: &str, date: &str, columns: u8) -> Self {
+    fn new(client: &str, title: Option<String>, date: &str, columns: u8, protocol: Protocol) -> Self {
         Self {
             phase: Phase::Scanning,
             files: Vec::new(),
-            scroll: 0,
+            selected: 0,
+            page: 10,
+            query: String::new(),
+            searching: false,
+            kind_filter: KindFilter::All,
+            status_filter: StatusFilter::All,
+            details: true,
             tick: 0,
             total_found: 0,
             processed_count: 0,
             failed_count: 0,
+            started: None,
+            workers: rayon::current_num_threads(),
             client: client.to_string(),
+            title,
             date: date.to_string(),
             columns,
+            hero: None,
+            editing: None,
+            edited: Vec::new(),
+            reordered: false,
+            thumbs: None,
+            protocol,
+            preview: None,
+            reselect: None,
+            rendered_at: None,
             output_path: String::new(),
+            revision: 0,
+            package: None,
             error_msg: None,
         }
     }
@@ -89,133 +306,483 @@ impl App {
     fn spinner(&self) -> char {
         SPINNER[(self.tick as usize / 2) % SPINNER.len()]
     }
+
+    /// Files per second since processing started, and the estimated time
+    /// left at that rate.
+    fn throughput(&self) -> Option<(f64, Duration)> {
+        let elapsed = self.started?.elapsed().as_secs_f64();
+        if self.processed_count == 0 || elapsed <= 0.0 {
+            return None;
+        }
+        let rate = self.processed_count as f64 / elapsed;
+        let remaining = self.total_found.saturating_sub(self.processed_count) as f64;
+        Some((rate, Duration::from_secs_f64(remaining / rate)))
+    }
+
+    /// Indices into `files` that pass the search and filters.
+    fn visible(&self) -> Vec<usize> {
+        let query = self.query.to_lowercase();
+        self.files
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| self.kind_filter.matches(f) && self.status_filter.matches(f))
+            .filter(|(_, f)| query.is_empty() || f.rel_path.to_lowercase().contains(&query))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Kinds of failure among the files, in a stable order.
+    fn failure_kinds(&self) -> Vec<ErrorKind> {
+        let mut kinds: Vec<ErrorKind> = self
+            .files
+            .iter()
+            .filter_map(|f| match f.status {
+                FileStatus::Failed(ref err) => Some(err.kind()),
+                _ => None,
+            })
+            .collect();
+        kinds.sort();
+        kinds.dedup();
+        kinds
+    }
+
+    /// Filters `s` steps through: each kind of failure after all failures,
+    /// then QC issues and duplicates when there can be any.
+    fn status_filters(&self, deep_check: bool) -> Vec<StatusFilter> {
+        let mut filters = vec![StatusFilter::All, StatusFilter::Ok, StatusFilter::Failed(None)];
+        filters.extend(self.failure_kinds().into_iter().map(|k| StatusFilter::Failed(Some(k))));
+        if deep_check {
+            filters.push(StatusFilter::Qc);
+        }
+        if self.files.iter().any(|f| f.duplicate.is_some()) {
+            filters.push(StatusFilter::Duplicates);
+        }
+        filters.push(StatusFilter::Excluded);
+        filters
+    }
+
+    /// Mark the files that duplicate each other, once assets come or go.
+    /// With `skip`, all but the kept copy are left off the contact sheet.
+    fn find_duplicates(&mut self, skip: bool) {
+        let (ids, assets): (Vec<usize>, Vec<&scan::Asset>) = self
+            .files
+            .iter()
+            .enumerate()
+            .filter_map(|(i, f)| Some((i, f.asset.as_ref()?)))
+            .unzip();
+        let groups = find_duplicates(assets);
+
+        for f in &mut self.files {
+            f.duplicate = None;
+        }
+        for group in groups {
+            let names: Vec<String> = group
+                .members
+                .iter()
+                .map(|&m| self.files[ids[m]].filename.clone())
+                .collect();
+            for (n, &m) in group.members.iter().enumerate() {
+                let others = names.iter().enumerate().filter(|&(o, _)| o != n).map(|(_, name)| name.clone());
+                self.files[ids[m]].duplicate = Some(Duplicate {
+                    matched: group.matched,
+                    others: others.collect(),
+                    skipped: skip && group.copies.iter().any(|&(copy, _)| copy == m),
+                });
+            }
+        }
+    }
+
+    fn is_filtered(&self) -> bool {
+        !self.query.is_empty()
+            || self.kind_filter != KindFilter::All
+            || self.status_filter != StatusFilter::All
+    }
+
+    /// The selected entry, if the filters haven't hidden it.
+    fn current(&self) -> Option<&FileEntry> {
+        self.visible()
+            .contains(&self.selected)
+            .then(|| &self.files[self.selected])
+    }
+
+    fn current_mut(&mut self) -> Option<&mut FileEntry> {
+        match self.visible().contains(&self.selected) {
+            true => self.files.get_mut(self.selected),
+            false => None,
+        }
+    }
+
+    /// Move the selection `delta` rows through the filtered list. A hidden
+    /// selection snaps to the nearest visible entry after it first.
+    fn select_by(&mut self, delta: isize) {
+        let visible = self.visible();
+        if visible.is_empty() {
+            return;
+        }
+        let pos = match visible.binary_search(&self.selected) {
+            Ok(pos) => pos as isize + delta,
+            Err(pos) => pos.min(visible.len() - 1) as isize,
+        };
+        self.selected = visible[pos.clamp(0, visible.len() as isize - 1) as usize];
+    }
+
+    fn select_first(&mut self) {
+        if let Some(&first) = self.visible().first() {
+            self.selected = first;
+        }
+    }
+
+    fn select_last(&mut self) {
+        if let Some(&last) = self.visible().last() {
+            self.selected = last;
+        }
+    }
+
+    fn selected_thumbnail(&self) -> Option<&Path> {
+        self.current()?
+            .asset
+            .as_ref()?
+            .thumbnail_path
+            .as_deref()
+    }
+
+    /// Decode the selected file's thumbnail if it isn't cached already.
+    fn load_preview(&mut self) {
+        let Some(path) = self.selected_thumbnail() else {
+            self.preview = None;
+            return;
+        };
+        if self.preview.as_ref().is_some_and(|(p, _)| p == path) {
+            return;
+        }
+        let path = path.to_path_buf();
+        self.preview = image::open(&path).ok().map(|img| (path, img));
+    }
+
+    fn entry_mut(&mut self, id: usize) -> Option<&mut FileEntry> {
+        self.files.iter_mut().find(|f| f.id == id)
+    }
+
+    /// Processed assets that are in the proof, in review order.
+    fn curated(&self) -> Vec<scan::Asset> {
+        self.files
+            .iter()
+            .filter(|f| f.included)
+            .filter_map(|f| f.asset.clone())
+            .collect()
+    }
+
+    fn field(&self, field: Field) -> String {
+        match field {
+            Field::Client => self.client.clone(),
+            Field::Title => self.title.clone().unwrap_or_default(),
+            Field::Date => self.date.clone(),
+        }
+    }
+
+    fn set_field(&mut self, field: Field, value: String) {
+        let value = value.trim().to_string();
+        if value == self.field(field) {
+            return;
+        }
+        match field {
+            Field::Client if !value.is_empty() => self.client = value,
+            Field::Title => self.title = (!value.is_empty()).then_some(value),
+            Field::Date if !value.is_empty() => self.date = value,
+            _ => return,
+        }
+        if !self.edited.contains(&field) {
+            self.edited.push(field);
+        }
+    }
+
+    /// Swap the selected file with its neighbour in the filtered list.
+    fn move_selected(&mut self, down: bool) {
+        let visible = self.visible();
+        let Ok(pos) = visible.binary_search(&self.selected) else {
+            return;
+        };
+        let to = if down {
+            pos + 1
+        } else {
+            match pos.checked_sub(1) {
+                Some(to) => to,
+                None => return,
+            }
+        };
+        if let Some(&to) = visible.get(to) {
+            self.files.swap(self.selected, to);
+            self.selected = to;
+            self.reordered = true;
+        }
+    }
+}
+
+/// Everything needed to render once review is over.
+struct Session {
+    input: PathBuf,
+    output: Option<PathBuf>,
+    /// Gallery folder, with `--html`.
+    html: Option<PathBuf>,
+    /// ZIP and README, with `--package`.
+    package: Option<PathBuf>,
+    package_readme: Option<PathBuf>,
+    project: config::Config,
+    auto_orient: bool,
+    skip_duplicates: bool,
+    review: bool,
+    watching: bool,
+    /// Written next to the PDF, with `--manifest-out`.
+    manifest: Option<Manifest>,
+    /// Compared with for the changes page, with `diff` or `--previous`.
+    previous: Option<Previous>,
+    /// Number of this session's delivery, from the delivery log.
+    revision: u32,
+    /// Names the PDF when there's no `output`.
+    output_template: OutputTemplate,
+    /// Also used to retry failed files during review.
+    opts: scan::ProcessOptions,
+    tx: mpsc::Sender<Msg>,
 }
 
 // ── Entry point ────────────────────────────────────────────
 
 pub fn run(cli: Cli) -> Result<()> {
-    let date = cli
-        .date
-        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
-    let client = cli.client.unwrap_or_else(|| String::from("Delivery"));
+    let input = cli.input().to_path_buf();
+    let project = config::Config::load(&input)?;
+    let details = cli.details(&project);
     let columns = cli.columns;
 
-    let output = cli.output.unwrap_or_else(|| {
-        let slug = client.to_lowercase().replace(' ', "-");
-        std::path::PathBuf::from(format!("{slug}-delivery-{date}.pdf"))
-    });
-
-    let auto_orient = cli.auto_orient;
-    let config = pdf::PdfConfig {
-        client: client.clone(),
-        title: cli.title.clone(),
-        date: date.clone(),
-        columns,
-        auto_orient,
-    };
+    let opts = cli.process_options(&project);
 
     let (tx, rx) = mpsc::channel::<Msg>();
+    let session = Session {
+        input: input.clone(),
+        output: cli.output.clone(),
+        html: cli.html.clone(),
+        package: cli.package.clone(),
+        package_readme: cli.package_readme.clone(),
+        project,
+        auto_orient: cli.auto_orient,
+        skip_duplicates: cli.skip_duplicates,
+        review: !cli.no_review,
+        watching: cli.is_watch(),
+        manifest: cli.manifest_out.is_some().then(|| cli.manifest()),
+        previous: cli.previous().map(Previous::load).transpose()?,
+        revision: deliveries::Log::load(&input)?.next_revision(),
+        output_template: cli.template(),
+        opts: opts.clone(),
+        tx: tx.clone(),
+    };
+    let protocol = Protocol::select(cli.graphics);
+    let mut app = App::new(&details.client, details.title, &details.date, columns, protocol);
 
     // Spawn background pipeline
-    let input = cli.input.clone();
-    let output_bg = output.clone();
-    std::thread::spawn(move || {
-        if let Err(e) = pipeline(tx.clone(), &input, &config, &output_bg) {
-            let _ = tx.send(Msg::Error(format!("{e:#}")));
-        }
-    });
+    if let Some(crate::cli::Command::Watch { debounce, .. }) = cli.command {
+        let thumbs = tempfile::tempdir()?;
+        let thumb_dir = thumbs.path().to_path_buf();
+        app.thumbs = Some(thumbs);
+        std::thread::spawn(move || {
+            let result = watch::run(&cli, debounce, &thumb_dir, &opts.cancel, |event| {
+                forward(&tx, event)
+            });
+            report(&tx, result);
+        });
+    } else {
+        std::thread::spawn(move || report(&tx, pipeline(&tx, &input, &opts)));
+    }
 
     // Run TUI
     let mut terminal = ratatui::init();
-    let mut app = App::new(&client, &date, columns);
-    let result = event_loop(&mut terminal, &mut app, &rx);
+    crossterm::execute!(std::io::stdout(), EnableMouseCapture)?;
+    let result = event_loop(&mut terminal, &mut app, &rx, &session);
+    if protocol == Protocol::Kitty {
+        let _ = graphics::clear(&mut std::io::stdout(), protocol, Rect::default());
+    }
+    let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
     ratatui::restore();
     result
 }
 
+/// Forward how a background job ended.
+fn report(tx: &mpsc::Sender<Msg>, result: Result<()>) {
+    match result {
+        Ok(()) => {}
+        Err(e) if cancel::is_cancelled(&e) => {
+            let _ = tx.send(Msg::Cancelled);
+        }
+        Err(e) => {
+            let _ = tx.send(Msg::Error(format!("{e:#}")));
+        }
+    }
+}
+
+/// Translate watch mode progress into the messages the pipeline sends.
+fn forward(tx: &mpsc::Sender<Msg>, event: watch::Event) {
+    let msgs = match event {
+        watch::Event::Scanning => vec![Msg::Rescan],
+        watch::Event::Found(found) => {
+            let total = found.len();
+            found
+                .into_iter()
+                .map(|(path, kind)| Msg::AssetFound { path, kind })
+                .chain([Msg::ScanDone { total }])
+                .collect()
+        }
+        watch::Event::Progress(progress) => vec![match progress {
+            scan::Progress::Processing { index } => Msg::Processing { index },
+            scan::Progress::Processed { index, asset } => Msg::Processed { index, asset },
+            scan::Progress::Failed { index, error } => Msg::Failed { index, error },
+        }],
+        watch::Event::Rendering => vec![Msg::Rendering],
+        watch::Event::Rendered { output, revision, summary } => vec![Msg::Done {
+            output: output.display().to_string(),
+            revision,
+            total: summary.total_files,
+            package: None,
+        }],
+        watch::Event::Unchanged => vec![Msg::Unchanged],
+        watch::Event::Warning(message) => vec![Msg::Warning(message)],
+        watch::Event::Error(e) => vec![Msg::Error(e)],
+    };
+    for msg in msgs {
+        let _ = tx.send(msg);
+    }
+}
+
 fn event_loop(
     terminal: &mut ratatui::DefaultTerminal,
     app: &mut App,
     rx: &mpsc::Receiver<Msg>,
+    session: &Session,
 ) -> Result<()> {
     let tick_rate = Duration::from_millis(80);
     let mut last_tick = Instant::now();
+    // What the graphics protocol last drew, so it's only redrawn on change.
+    let mut shown: Option<(PathBuf, Rect)> = None;
 
     loop {
-        terminal.draw(|f| draw(f, app))?;
+        app.load_preview();
+        let mut image_area = None;
+        terminal.draw(|f| image_area = draw(f, app))?;
+        show_preview(app, image_area, &mut shown)?;
+        // Header, progress and footer take 9 rows, the list border 2 more.
+        app.page = (terminal.size()?.height as usize).saturating_sub(11).max(1);
 
         let timeout = tick_rate.saturating_sub(last_tick.elapsed());
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
-                        KeyCode::Char('j') | KeyCode::Down => {
-                            if app.scroll < app.files.len().saturating_sub(1) {
-                                app.scroll += 1;
-                            }
-                        }
-                        KeyCode::Char('k') | KeyCode::Up => {
-                            app.scroll = app.scroll.saturating_sub(1);
-                        }
-                        KeyCode::Enter
-                            if app.phase == Phase::Complete || app.phase == Phase::Failed =>
-                        {
-                            return Ok(());
-                        }
-                        _ => {}
-                    }
+            match event::read()? {
+                Event::Key(key)
+                    if key.kind == KeyEventKind::Press && handle_key(app, key, session) =>
+                {
+                    return Ok(());
                 }
+                Event::Mouse(mouse) => match mouse.kind {
+                    MouseEventKind::ScrollDown => app.select_by(3),
+                    MouseEventKind::ScrollUp => app.select_by(-3),
+                    _ => {}
+                },
+                _ => {}
             }
         }
 
         // Drain messages from background thread
+        let mut processed = false;
         while let Ok(msg) = rx.try_recv() {
             match msg {
-                Msg::AssetFound { filename, kind } => {
+                Msg::AssetFound { path, kind } => {
+                    let filename = path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("?")
+                        .to_string();
+                    let id = app.files.len();
+                    if session.project.is_hero(&session.input, &path) {
+                        app.hero = Some(id);
+                    }
+                    if app.reselect.as_ref() == Some(&path) {
+                        app.selected = id;
+                    }
                     app.files.push(FileEntry {
+                        id,
+                        included: !session.project.is_excluded(&session.input, &path),
+                        rel_path: config::relative(&session.input, &path),
+                        path,
                         filename,
                         kind,
                         status: FileStatus::Pending,
+                        asset: None,
+                        duplicate: None,
                     });
                     app.total_found = app.files.len();
                 }
                 Msg::ScanDone { total } => {
                     app.total_found = total;
                     app.phase = Phase::Processing;
+                    app.started = Some(Instant::now());
                 }
                 Msg::Processing { index } => {
-                    if let Some(f) = app.files.get_mut(index) {
+                    if let Some(f) = app.entry_mut(index) {
                         f.status = FileStatus::Processing;
                     }
                 }
-                Msg::Processed { index } => {
-                    if let Some(f) = app.files.get_mut(index) {
+                Msg::Processed { index, asset } => {
+                    if let Some(f) = app.entry_mut(index) {
                         f.status = FileStatus::Done;
+                        f.asset = Some(*asset);
                     }
                     app.processed_count += 1;
+                    processed = true;
                 }
                 Msg::Failed { index, error } => {
-                    if let Some(f) = app.files.get_mut(index) {
+                    if let Some(f) = app.entry_mut(index) {
                         f.status = FileStatus::Failed(error);
+                        // A placeholder from before a failed retry would
+                        // carry the old error
+                        f.asset = None;
                     }
                     app.failed_count += 1;
                     app.processed_count += 1;
                 }
-                Msg::Rendering => {
-                    app.phase = Phase::Rendering;
+                Msg::Ready { thumbs } => {
+                    app.thumbs = Some(thumbs);
+                    app.phase = Phase::Review;
+                    if !session.review {
+                        start_render(app, session);
+                    }
+                }
+                Msg::Rescan => {
+                    app.reselect = app.files.get(app.selected).map(|f| f.path.clone());
+                    app.files.clear();
+                    app.hero = None;
+                    app.total_found = 0;
+                    app.processed_count = 0;
+                    app.failed_count = 0;
+                    app.started = None;
+                    app.error_msg = None;
+                    app.phase = Phase::Scanning;
                 }
-                Msg::Done { output, total } => {
-                    app.phase = Phase::Complete;
+                Msg::Rendering => app.phase = Phase::Rendering,
+                Msg::Unchanged => app.phase = Phase::Watching,
+                Msg::Done { output, revision, total, package } => {
+                    app.phase = if session.watching { Phase::Watching } else { Phase::Complete };
+                    app.rendered_at = Some(chrono::Local::now().format("%H:%M:%S").to_string());
                     app.output_path = output;
+                    app.revision = revision;
+                    app.package = package;
                     app.processed_count = total;
                 }
+                Msg::Cancelled => return Ok(()),
+                Msg::Warning(message) => app.error_msg = Some(message),
                 Msg::Error(e) => {
                     app.phase = Phase::Failed;
                     app.error_msg = Some(e);
                 }
             }
         }
+        if processed {
+            app.find_duplicates(session.skip_duplicates);
+        }
 
         if last_tick.elapsed() >= tick_rate {
             app.tick += 1;
@@ -224,75 +791,375 @@ fn event_loop(
     }
 }
 
+/// Draw the selected thumbnail with a terminal graphics protocol. Half
+/// blocks are drawn by ratatui itself in `draw_preview`.
+fn show_preview(app: &App, area: Option<Rect>, shown: &mut Option<(PathBuf, Rect)>) -> Result<()> {
+    if app.protocol == Protocol::HalfBlocks {
+        return Ok(());
+    }
+
+    let wanted = match (&app.preview, area) {
+        (Some((path, img)), Some(area)) => Some((path, img, area)),
+        _ => None,
+    };
+    let unchanged = match (&wanted, &*shown) {
+        (Some((path, _, area)), Some((shown_path, shown_area))) => {
+            *path == shown_path && area == shown_area
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        return Ok(());
+    }
+
+    let mut out = std::io::stdout();
+    match wanted {
+        Some((path, img, area)) => {
+            graphics::draw(&mut out, app.protocol, img, area)?;
+            *shown = Some((path.clone(), area));
+        }
+        None => {
+            if let Some((_, area)) = shown.take() {
+                graphics::clear(&mut out, app.protocol, area)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply a key press. Returns true when the TUI should exit.
+fn handle_key(app: &mut App, key: KeyEvent, session: &Session) -> bool {
+    if let Some(edit) = app.editing.as_mut() {
+        match key.code {
+            KeyCode::Char(c) => edit.buffer.push(c),
+            KeyCode::Backspace => {
+                edit.buffer.pop();
+            }
+            KeyCode::Tab => {
+                let (field, value) = (edit.field, std::mem::take(&mut edit.buffer));
+                app.set_field(field, value);
+                app.editing = Some(Edit {
+                    field: field.next(),
+                    buffer: app.field(field.next()),
+                });
+            }
+            KeyCode::Enter => {
+                if let Some(edit) = app.editing.take() {
+                    app.set_field(edit.field, edit.buffer);
+                }
+            }
+            KeyCode::Esc => app.editing = None,
+            _ => {}
+        }
+        return false;
+    }
+
+    if app.searching {
+        match key.code {
+            KeyCode::Char(c) => app.query.push(c),
+            KeyCode::Backspace => {
+                app.query.pop();
+            }
+            KeyCode::Down => app.select_by(1),
+            KeyCode::Up => app.select_by(-1),
+            KeyCode::Enter => app.searching = false,
+            KeyCode::Esc => {
+                app.query.clear();
+                app.searching = false;
+            }
+            _ => {}
+        }
+        app.select_by(0);
+        return false;
+    }
+
+    let page = app.page as isize;
+    match key.code {
+        // Esc clears an active search before it quits or cancels
+        KeyCode::Esc if !app.query.is_empty() => app.query.clear(),
+        KeyCode::Char('q') | KeyCode::Esc => match app.phase {
+            // A second press while cancelling quits without waiting for the
+            // pipeline to wind down.
+            Phase::Review | Phase::Complete | Phase::Watching | Phase::Failed | Phase::Cancelling => {
+                return true;
+            }
+            _ => {
+                session.opts.cancel.cancel();
+                app.phase = Phase::Cancelling;
+            }
+        },
+        KeyCode::Char('j') | KeyCode::Down => app.select_by(1),
+        KeyCode::Char('k') | KeyCode::Up => app.select_by(-1),
+        KeyCode::PageDown => app.select_by(page),
+        KeyCode::PageUp => app.select_by(-page),
+        KeyCode::Home | KeyCode::Char('g') => app.select_first(),
+        KeyCode::End | KeyCode::Char('G') => app.select_last(),
+        KeyCode::Char('/') => {
+            app.searching = true;
+            app.select_by(0);
+        }
+        KeyCode::Char('f') => {
+            app.kind_filter = app.kind_filter.next();
+            app.select_by(0);
+        }
+        KeyCode::Char('s') => {
+            app.status_filter = app.status_filter.next(&app.status_filters(session.opts.deep_check));
+            app.select_by(0);
+        }
+        KeyCode::Char('d') => app.details = !app.details,
+        KeyCode::Char('J') if app.phase == Phase::Review => app.move_selected(true),
+        KeyCode::Char('K') if app.phase == Phase::Review => app.move_selected(false),
+        KeyCode::Char(' ') if app.phase == Phase::Review => {
+            if let Some(f) = app.current_mut().filter(|f| f.asset.is_some()) {
+                f.included = !f.included;
+            }
+        }
+        KeyCode::Char('r') if app.phase == Phase::Review => {
+            if let Some(f) = app.current().filter(|f| matches!(f.status, FileStatus::Failed(_))) {
+                let id = f.id;
+                retry(app, session, &[id]);
+            }
+        }
+        KeyCode::Char('R') if app.phase == Phase::Review => {
+            let ids: Vec<usize> = app
+                .files
+                .iter()
+                .filter(|f| matches!(f.status, FileStatus::Failed(_)))
+                .map(|f| f.id)
+                .collect();
+            retry(app, session, &ids);
+        }
+        KeyCode::Char('u') if app.phase == Phase::Review => {
+            if let Some(f) = app.current_mut() {
+                if let FileStatus::Failed(ref err) = f.status {
+                    f.asset = match f.asset {
+                        Some(_) => None,
+                        None => {
+                            f.included = true;
+                            Some(scan::placeholder(&f.path, f.kind, err.clone()))
+                        }
+                    };
+                }
+            }
+        }
+        KeyCode::Char('h') if app.phase == Phase::Review => {
+            let readable = |f: &&FileEntry| f.asset.as_ref().is_some_and(|a| a.error.is_none());
+            if let Some(f) = app.current().filter(readable) {
+                app.hero = if app.hero == Some(f.id) { None } else { Some(f.id) };
+            }
+        }
+        KeyCode::Char('e') if app.phase == Phase::Review => {
+            app.editing = Some(Edit {
+                field: Field::Client,
+                buffer: app.field(Field::Client),
+            });
+        }
+        KeyCode::Enter if app.phase == Phase::Review => start_render(app, session),
+        KeyCode::Enter if app.phase == Phase::Complete || app.phase == Phase::Failed => {
+            return true;
+        }
+        _ => {}
+    }
+    false
+}
+
 // ── Background pipeline ────────────────────────────────────
 
-fn pipeline(
-    tx: mpsc::Sender<Msg>,
-    input: &std::path::Path,
-    config: &pdf::PdfConfig,
-    output: &std::path::Path,
-) -> Result<()> {
+fn pipeline(tx: &mpsc::Sender<Msg>, input: &Path, opts: &scan::ProcessOptions) -> Result<()> {
     // 1. Scan
-    let found = scan::discover(input)?;
+    let mut found = scan::discover(input)?;
+    let project = config::Config::load(input)?;
+    project.apply_order(input, &mut found);
+
     for (path, kind) in &found {
-        let fname = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("?")
-            .to_string();
-        let kind_str = match kind {
-            scan::AssetKind::Image => "image",
-            scan::AssetKind::Video => "video",
-        };
         let _ = tx.send(Msg::AssetFound {
-            filename: fname,
-            kind: kind_str.into(),
+            path: path.clone(),
+            kind: *kind,
         });
     }
     let _ = tx.send(Msg::ScanDone {
         total: found.len(),
     });
 
-    // 2. Process sequentially (for per-file TUI updates)
+    // 2. Process in parallel, forwarding per-file progress. Excluded files
+    // are processed too so they can be brought back during review.
     let thumb_dir = tempfile::tempdir()?;
-    let mut assets = Vec::with_capacity(found.len());
+    scan::process_all(&found, thumb_dir.path(), opts, |progress| {
+        let _ = tx.send(match progress {
+            scan::Progress::Processing { index } => Msg::Processing { index },
+            scan::Progress::Processed { index, asset } => Msg::Processed { index, asset },
+            scan::Progress::Failed { index, error } => Msg::Failed { index, error },
+        });
+    });
 
-    for (i, (path, kind)) in found.iter().enumerate() {
-        let _ = tx.send(Msg::Processing { index: i });
+    opts.cancel.check()?;
+    let _ = tx.send(Msg::Ready { thumbs: thumb_dir });
+    Ok(())
+}
 
-        match scan::process_one(path, *kind, thumb_dir.path(), i, true, config.auto_orient) {
-            Ok(asset) => {
-                let _ = tx.send(Msg::Processed { index: i });
-                assets.push(asset);
-            }
-            Err(e) => {
-                let _ = tx.send(Msg::Failed {
-                    index: i,
-                    error: format!("{e:#}"),
-                });
-            }
+/// Process failed files again on a background thread, reporting through
+/// the same messages as the pipeline.
+fn retry(app: &mut App, session: &Session, ids: &[usize]) {
+    let Some(thumb_dir) = app.thumbs.as_ref().map(|t| t.path().to_path_buf()) else {
+        return;
+    };
+    let mut jobs = Vec::new();
+    for &id in ids {
+        if let Some(f) = app.entry_mut(id) {
+            f.status = FileStatus::Processing;
+            jobs.push((id, f.path.clone(), f.kind));
         }
     }
+    app.failed_count -= jobs.len();
+    app.processed_count -= jobs.len();
+    app.error_msg = None;
 
-    // Sort to match natural order
-    assets.sort_by(|a, b| natord::compare(&a.filename, &b.filename));
+    let tx = session.tx.clone();
+    let opts = session.opts.clone();
+    std::thread::spawn(move || {
+        jobs.par_iter().for_each(|(index, path, kind)| {
+            let msg = match scan::process_one(path, *kind, &thumb_dir, *index, &opts) {
+                Ok(asset) => Msg::Processed { index: *index, asset: Box::new(asset) },
+                Err(ProcessError::Cancelled) => return,
+                Err(error) => Msg::Failed { index: *index, error },
+            };
+            let _ = tx.send(msg);
+        });
+    });
+}
 
-    // 3. Render PDF
-    let _ = tx.send(Msg::Rendering);
-    pdf::render(&assets, config, output)?;
+/// Save the review choices to `proof.toml` and render the curated assets
+/// on a background thread.
+fn start_render(app: &mut App, session: &Session) {
+    if app.files.iter().any(|f| matches!(f.status, FileStatus::Processing)) {
+        app.error_msg = Some("Wait for retries to finish before rendering".into());
+        return;
+    }
+    let assets = app.curated();
+    if assets.is_empty() {
+        app.error_msg = Some("Nothing to render: include at least one file".into());
+        return;
+    }
+    app.error_msg = None;
 
-    let out_str = output.display().to_string();
-    let total = assets.len();
-    let _ = tx.send(Msg::Done {
-        output: out_str,
-        total,
+    let mut project = session.project.clone();
+    let root = &session.input;
+    if app.edited.contains(&Field::Client) {
+        project.client = Some(app.client.clone());
+    }
+    if app.edited.contains(&Field::Title) {
+        project.title = app.title.clone();
+    }
+    if app.edited.contains(&Field::Date) {
+        project.date = Some(app.date.clone());
+    }
+    // Settings for files that aren't here any more are left alone
+    let listed: HashSet<String> = app.files.iter().map(|f| config::relative(root, &f.path)).collect();
+    let hero = app
+        .files
+        .iter()
+        .find(|f| Some(f.id) == app.hero)
+        .map(|f| config::relative(root, &f.path));
+    if hero.is_some() || project.hero.as_ref().is_some_and(|h| listed.contains(h)) {
+        project.hero = hero;
+    }
+    let mut exclude: Vec<String> = project.exclude.iter().filter(|p| !listed.contains(*p)).cloned().collect();
+    exclude.extend(app.files.iter().filter(|f| !f.included).map(|f| config::relative(root, &f.path)));
+    let unordered = |paths: &[String]| paths.iter().cloned().collect::<HashSet<String>>();
+    if unordered(&exclude) != unordered(&project.exclude) {
+        project.exclude = exclude;
+    }
+    if app.reordered {
+        project.order = app.files.iter().map(|f| config::relative(root, &f.path)).collect();
+    }
+    if let Err(e) = project.save_changes(root, &session.project) {
+        app.error_msg = Some(format!("{e:#}"));
+    }
+
+    let hero = app
+        .files
+        .iter()
+        .find(|f| Some(f.id) == app.hero && f.included)
+        .map(|f| f.path.clone());
+    let mut config = pdf::PdfConfig::new(app.client.clone(), app.date.clone());
+    config.title = app.title.clone();
+    config.columns = app.columns;
+    config.auto_orient = session.auto_orient;
+    config.hero = hero;
+    config.skip_duplicates = session.skip_duplicates;
+    config.changes = session
+        .previous
+        .as_ref()
+        .map(|previous| changes::compare(previous, &assets, &session.input));
+    config.revision = Some(session.revision);
+    let fields = OutputFields {
+        client: &app.client,
+        title: app.title.as_deref(),
+        date: &app.date,
+        revision: session.revision,
+        files: assets.len(),
+        input: &session.input,
+    };
+    let output = output_for(session.output.as_deref(), &session.output_template, &fields);
+
+    app.phase = Phase::Rendering;
+    let tx = session.tx.clone();
+    let cancel = session.opts.cancel.clone();
+    let manifest = session.manifest.clone();
+    let html = session.html.clone();
+    let zip = session.package.clone();
+    let readme = session.package_readme.clone();
+    let root = session.input.clone();
+    let revision = session.revision;
+    std::thread::spawn(move || {
+        let result = (|| -> Result<()> {
+            pdf::render(&assets, &config, &output, &cancel)?;
+            let delivery = Delivery::new(revision, &config.client, &config.date, &assets, &root, &output);
+            if let Some(message) = deliveries::record_or_warn(&root, delivery) {
+                let _ = tx.send(Msg::Warning(message));
+            }
+            if let Some(dir) = html {
+                html::render(&assets, &config, &dir, &cancel)?;
+            }
+            if let Some(manifest) = manifest {
+                manifest.write(&assets, &root)?;
+            }
+            let package = match zip {
+                Some(zip) => {
+                    let contents = PackageContents {
+                        assets: &assets,
+                        root: &root,
+                        proof: &output,
+                        readme: readme.as_deref(),
+                    };
+                    let packaged = write_package(&zip, &contents, &cancel)?;
+                    Some(format!(
+                        "{} ({} files, {}, verified)",
+                        zip.display(),
+                        packaged.files,
+                        humansize::format_size(packaged.bytes, humansize::BINARY)
+                    ))
+                }
+                None => None,
+            };
+            let _ = tx.send(Msg::Done {
+                output: output.display().to_string(),
+                revision,
+                total: assets.len(),
+                package,
+            });
+            Ok(())
+        })();
+        report(&tx, result);
     });
-    Ok(())
 }
 
 // ── Drawing ────────────────────────────────────────────────
 
-fn draw(f: &mut Frame, app: &App) {
+/// Returns where the thumbnail preview should go when it is drawn with a
+/// graphics protocol rather than by ratatui.
+fn draw(f: &mut Frame, app: &App) -> Option<Rect> {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -304,21 +1171,35 @@ fn draw(f: &mut Frame, app: &App) {
         .split(f.area());
 
     draw_header(f, app, chunks[0]);
-    draw_files(f, app, chunks[1]);
     draw_progress(f, app, chunks[2]);
     draw_footer(f, app, chunks[3]);
+
+    // Side pane only when there's room for both it and the file list
+    if !app.details || chunks[1].width < 80 {
+        draw_files(f, app, chunks[1]);
+        return None;
+    }
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(40), Constraint::Percentage(40)])
+        .split(chunks[1]);
+    draw_files(f, app, body[0]);
+    draw_details(f, app, body[1])
 }
 
 fn draw_header(f: &mut Frame, app: &App, area: Rect) {
     let phase_str = match app.phase {
         Phase::Scanning => format!("{} Scanning...", app.spinner()),
         Phase::Processing => format!("{} Processing...", app.spinner()),
+        Phase::Review => "Review".into(),
         Phase::Rendering => format!("{} Rendering PDF...", app.spinner()),
+        Phase::Cancelling => format!("{} Cancelling…", app.spinner()),
         Phase::Complete => "Done".into(),
+        Phase::Watching => "Watching".into(),
         Phase::Failed => "Failed".into(),
     };
 
-    let header = Paragraph::new(Line::from(vec![
+    let mut spans = vec![
         Span::styled(
             " proof ",
             Style::default()
@@ -332,20 +1213,27 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
                 .fg(Color::White)
                 .add_modifier(Modifier::BOLD),
         ),
+    ];
+    if let Some(ref title) = app.title {
+        spans.push(Span::styled(format!("  {title}"), Style::default().fg(Color::White)));
+    }
+    spans.extend([
         Span::styled(
-            format!("  {}  cols:{}  ", app.date, app.columns),
+            format!("  {}  cols:{}  jobs:{}  ", app.date, app.columns, app.workers),
             Style::default().fg(Color::DarkGray),
         ),
         Span::styled(
             phase_str,
             Style::default().fg(match app.phase {
-                Phase::Complete => Color::Green,
+                Phase::Complete | Phase::Watching => Color::Green,
                 Phase::Failed => Color::Red,
+                Phase::Review => Color::Cyan,
                 _ => Color::Yellow,
             }),
         ),
-    ]))
-    .block(
+    ]);
+
+    let header = Paragraph::new(Line::from(spans)).block(
         Block::default()
             .borders(Borders::BOTTOM)
             .border_style(Style::default().fg(Color::DarkGray)),
@@ -355,35 +1243,68 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn draw_files(f: &mut Frame, app: &App, area: Rect) {
-    let visible = (area.height as usize).saturating_sub(2);
-    let start = app.scroll.min(app.files.len().saturating_sub(visible));
+    let rows = (area.height as usize).saturating_sub(2);
+    let shown = app.visible();
+    // Keep the selection on screen, scrolling only once it reaches the end.
+    let pos = shown.iter().position(|&i| i == app.selected).unwrap_or(0);
+    let start = (pos + 1).saturating_sub(rows);
 
-    let items: Vec<ListItem> = app
-        .files
+    let items: Vec<ListItem> = shown
         .iter()
         .skip(start)
-        .take(visible)
-        .map(|entry| {
+        .take(rows)
+        .map(|&i| {
+            let entry = &app.files[i];
             let (icon, color) = match &entry.status {
                 FileStatus::Done => ("\u{2713} ", Color::Green),
                 FileStatus::Processing => ("\u{25CF} ", Color::Yellow),
-                FileStatus::Failed(_) => ("\u{2717} ", Color::Red),
+                FileStatus::Failed(err) => ("\u{2717} ", failure_color(err.kind())),
                 FileStatus::Pending => ("  ", Color::DarkGray),
             };
 
+            let mut name_style = Style::default().fg(color);
+            if !entry.included {
+                name_style = name_style.fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT);
+            }
+            if i == app.selected {
+                name_style = name_style.add_modifier(Modifier::REVERSED);
+            }
+
             let mut spans = vec![
                 Span::styled(icon, Style::default().fg(color)),
-                Span::styled(entry.filename.as_str(), Style::default().fg(color)),
+                Span::styled(entry.filename.as_str(), name_style),
                 Span::styled(
-                    format!("  {}", entry.kind),
+                    format!("  {}", entry.kind.to_string().to_lowercase()),
                     Style::default().fg(Color::DarkGray),
                 ),
             ];
 
+            if app.hero == Some(entry.id) {
+                spans.push(Span::styled("  \u{2605} cover", Style::default().fg(Color::Cyan)));
+            }
+            if entry.asset.as_ref().is_some_and(|a| a.error.is_some()) {
+                spans.push(Span::styled("  placeholder", Style::default().fg(Color::Yellow)));
+            }
+            if entry.asset.as_ref().is_some_and(|a| !a.warnings.is_empty()) {
+                spans.push(Span::styled("  \u{26A0} warning", Style::default().fg(Color::Yellow)));
+            }
+            if let Some(ref dup) = entry.duplicate {
+                spans.push(Span::styled(
+                    format!("  \u{29C9} {}", dup.matched.label()),
+                    Style::default().fg(Color::Blue),
+                ));
+            }
+            if let Some(issue) = entry.asset.as_ref().and_then(|a| a.issues().first()) {
+                spans.push(Span::styled(
+                    format!("  \u{26A0} {}", issue.kind.label()),
+                    Style::default().fg(Color::LightRed),
+                ));
+            }
+
             if let FileStatus::Failed(ref err) = entry.status {
                 spans.push(Span::styled(
-                    format!("  {err}"),
-                    Style::default().fg(Color::Red),
+                    format!("  {}", err.kind().label()),
+                    Style::default().fg(failure_color(err.kind())),
                 ));
             }
 
@@ -391,7 +1312,25 @@ fn draw_files(f: &mut Frame, app: &App, area: Rect) {
         })
         .collect();
 
-    let title = format!(" Files ({}) ", app.files.len());
+    let included = app.files.iter().filter(|f| f.included).count();
+    let mut title = if included < app.files.len() {
+        format!(" Files ({} of {} included) ", included, app.files.len())
+    } else {
+        format!(" Files ({}) ", app.files.len())
+    };
+    if app.is_filtered() {
+        title.push_str(&format!("{} shown ", shown.len()));
+        if !app.query.is_empty() {
+            title.push_str(&format!("/{} ", app.query));
+        }
+        if app.kind_filter != KindFilter::All {
+            let kind = if app.kind_filter == KindFilter::Image { "images" } else { "videos" };
+            title.push_str(&format!("{kind} "));
+        }
+        if app.status_filter != StatusFilter::All {
+            title.push_str(&format!("{} ", app.status_filter.label()));
+        }
+    }
     let list = List::new(items).block(
         Block::default()
             .title(title)
@@ -401,6 +1340,125 @@ fn draw_files(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(list, area);
 }
 
+/// Thumbnail above the full metadata and error text of the selected file.
+fn draw_details(f: &mut Frame, app: &App, area: Rect) -> Option<Rect> {
+    let block = Block::default()
+        .title(" Details ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let entry = app.current()?;
+    let meta = detail_lines(app, entry);
+    // Rows the text takes once wrapped, give or take word breaks
+    let width = inner.width.max(1) as usize;
+    let height: usize = meta.iter().map(|l| l.width().max(1).div_ceil(width)).sum();
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(height as u16 + 1)])
+        .split(inner);
+    f.render_widget(Paragraph::new(meta).wrap(Wrap { trim: false }), parts[1]);
+
+    let (_, img) = app.preview.as_ref()?;
+    let image_area = parts[0];
+    if image_area.height < 3 || image_area.width < 6 {
+        return None;
+    }
+    match app.protocol {
+        Protocol::HalfBlocks => {
+            f.render_widget(Paragraph::new(graphics::half_blocks(img, image_area)), image_area);
+            None
+        }
+        _ => Some(image_area),
+    }
+}
+
+fn detail_lines(app: &App, entry: &FileEntry) -> Vec<Line<'static>> {
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!(" {label:<11}"), Style::default().fg(Color::DarkGray)),
+            Span::raw(value),
+        ])
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!(" {}", entry.filename),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        row("Path", entry.path.display().to_string()),
+    ];
+    let mut state = String::from(if entry.included { "included" } else { "excluded" });
+    if entry.asset.as_ref().is_some_and(|a| a.error.is_some()) {
+        state.push_str(" as unreadable");
+    }
+    if app.hero == Some(entry.id) {
+        state.push_str(", cover");
+    }
+    lines.push(row("Proof", state));
+
+    if let FileStatus::Failed(ref err) = entry.status {
+        lines.push(Line::from(vec![
+            Span::styled(" Error      ", Style::default().fg(Color::DarkGray)),
+            Span::styled(err.to_string(), Style::default().fg(failure_color(err.kind()))),
+        ]));
+    }
+    let Some(ref a) = entry.asset else {
+        return lines;
+    };
+    for warning in &a.warnings {
+        lines.push(Line::from(vec![
+            Span::styled(" Warning    ", Style::default().fg(Color::DarkGray)),
+            Span::styled(warning.clone(), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+
+    match a.qc {
+        Some(ref issues) if issues.is_empty() => lines.push(Line::from(vec![
+            Span::styled(" QC         ", Style::default().fg(Color::DarkGray)),
+            Span::styled("decoded cleanly", Style::default().fg(Color::Green)),
+        ])),
+        Some(ref issues) => {
+            for issue in issues {
+                lines.push(Line::from(vec![
+                    Span::styled(" QC         ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format!("{}: {}", issue.kind.label(), issue.message),
+                        Style::default().fg(Color::LightRed),
+                    ),
+                ]));
+            }
+        }
+        None => {}
+    }
+
+    if let Some(ref dup) = entry.duplicate {
+        let mut text = format!("{} to {}", dup.matched.label(), dup.others.join(", "));
+        if dup.skipped {
+            text.push_str(", left off the contact sheet");
+        }
+        lines.push(row("Duplicate", text));
+    }
+
+    lines.push(row("Type", format!("{} {}", a.kind, a.format)));
+    lines.push(row("Resolution", a.resolution()));
+    lines.push(row("Size", format!("{} ({} bytes)", a.human_size(), a.file_size)));
+    if let Some(ref cs) = a.color_space {
+        lines.push(row("Colour", cs.clone()));
+    }
+    if let Some(d) = a.duration {
+        lines.push(row("Duration", format!("{}:{:02}", d as u64 / 60, d as u64 % 60)));
+    }
+    if let Some(ref codec) = a.codec {
+        lines.push(row("Codec", codec.clone()));
+    }
+    if !a.filmstrip.is_empty() {
+        lines.push(row("Filmstrip", format!("{} frames", a.filmstrip.len())));
+    }
+    lines
+}
+
 fn draw_progress(f: &mut Frame, app: &App, area: Rect) {
     let (ratio, label) = match app.phase {
         Phase::Scanning => (0.0, format!("Scanning... {} found", app.total_found)),
@@ -410,13 +1468,36 @@ fn draw_progress(f: &mut Frame, app: &App, area: Rect) {
             } else {
                 0.0
             };
-            (
-                r,
-                format!("{}/{} processed", app.processed_count, app.total_found),
-            )
+            let mut label = format!("{}/{} processed", app.processed_count, app.total_found);
+            if let Some((rate, eta)) = app.throughput() {
+                let eta = eta.as_secs();
+                label.push_str(&format!("  {rate:.1} files/s  ETA {}:{:02}", eta / 60, eta % 60));
+            }
+            (r, label)
         }
+        Phase::Review => (
+            1.0,
+            format!("{} assets ready  Enter: render PDF", app.curated().len()),
+        ),
         Phase::Rendering => (1.0, "Rendering PDF...".into()),
-        Phase::Complete => (1.0, format!("Complete: {}", app.output_path)),
+        Phase::Cancelling => (0.0, "Cancelling… waiting for workers to stop".into()),
+        Phase::Complete => {
+            let label = match app.package {
+                Some(ref package) => format!("Delivery {} complete: {}  Package: {package}", app.revision, app.output_path),
+                None => format!("Delivery {} complete: {}", app.revision, app.output_path),
+            };
+            (1.0, label)
+        }
+        Phase::Watching => {
+            let label = match app.rendered_at {
+                Some(ref at) => format!(
+                    "Delivery {}: {} assets in {} at {at}  watching for changes",
+                    app.revision, app.processed_count, app.output_path
+                ),
+                None => "Watching for changes".into(),
+            };
+            (1.0, label)
+        }
         Phase::Failed => (0.0, "Failed".into()),
     };
 
@@ -433,12 +1514,60 @@ fn draw_progress(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
+    if app.searching {
+        let line = Line::from(vec![
+            Span::styled(" / ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("{}\u{2581}", app.query), Style::default().fg(Color::White)),
+            Span::styled(
+                "  Enter: keep filter  Esc: clear",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        let footer = Paragraph::new(line).block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(footer, area);
+        return;
+    }
+
+    if let Some(ref edit) = app.editing {
+        let line = Line::from(vec![
+            Span::styled(
+                format!(" {}: ", edit.field.label()),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("{}\u{2581}", edit.buffer), Style::default().fg(Color::White)),
+            Span::styled(
+                "  Tab: next field  Enter: save  Esc: cancel",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        let footer = Paragraph::new(line).block(
+            Block::default()
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(footer, area);
+        return;
+    }
+
     let text = match app.phase {
-        Phase::Complete | Phase::Failed => " q/Enter: exit  j/k: scroll ",
-        _ => " q: cancel  j/k: scroll ",
+        Phase::Complete | Phase::Failed => " q/Enter: exit  j/k: scroll  /: search  f/s: filter ",
+        Phase::Cancelling => " q: quit now  j/k: scroll ",
+        Phase::Watching => " q: stop watching  j/k: scroll  /: search  f/s: filter  d: details ",
+        Phase::Review => " j/k: select  J/K: reorder  space: include  h: cover  e: edit  /: search  f/s: filter  d: details  Enter: render  q: quit ",
+        _ => " q: cancel  j/k: scroll  /: search  f/s: filter  d: details ",
     };
 
     let mut spans = vec![Span::styled(text, Style::default().fg(Color::DarkGray))];
+    if app.phase == Phase::Review && app.failed_count > 0 {
+        spans.push(Span::styled(
+            " r/R: retry one/all  u: include as unreadable ",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
 
     if app.failed_count > 0 {
         spans.push(Span::styled(
@@ -447,6 +1576,22 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
         ));
     }
 
+    let count = |test: fn(&scan::Asset) -> bool| app.files.iter().filter(|f| f.asset.as_ref().is_some_and(test)).count();
+    let issues = count(scan::Asset::has_problems);
+    if issues > 0 {
+        spans.push(Span::styled(
+            format!(" {issues} with qc issues "),
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    let unchecked = count(scan::Asset::is_unchecked);
+    if unchecked > 0 {
+        spans.push(Span::styled(
+            format!(" {unchecked} not checked "),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
     if let Some(ref err) = app.error_msg {
         spans.push(Span::styled(
             format!(" {err}"),
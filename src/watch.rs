@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use notify::event::{AccessKind, AccessMode};
+use notify::{EventKind, RecursiveMode, Watcher};
+use rayon::prelude::*;
+
+use proof::cancel::Cancel;
+use proof::changes::{self, Previous};
+use proof::config::{self, Config};
+use proof::deliveries::{self, Delivery, Log};
+use proof::error::ProcessError;
+use proof::{output_for, pdf};
+use proof::scan::{self, Asset, AssetKind};
+
+use crate::cli::Cli;
+use crate::progress::Summary;
+
+/// What `run` is doing, reported once per pass over the directory.
+pub enum Event {
+    /// A pass started because the directory changed.
+    Scanning,
+    /// Files going into this pass, in proof order. Progress indices refer
+    /// to this list.
+    Found(Vec<(PathBuf, AssetKind)>),
+    Progress(scan::Progress),
+    Rendering,
+    Rendered { output: PathBuf, revision: u32, summary: Summary },
+    /// Nothing that affects the proof changed, so it wasn't re-rendered.
+    Unchanged,
+    /// Something went wrong that doesn't stop the pass.
+    Warning(String),
+    /// The pass failed. Watching carries on and retries on the next change.
+    Error(String),
+}
+
+/// Modification time and size, compared to tell whether a file changed.
+type Stamp = (Option<SystemTime>, u64);
+
+struct Cached {
+    stamp: Stamp,
+    asset: Asset,
+}
+
+/// Everything the last render was made from.
+#[derive(PartialEq)]
+struct Snapshot {
+    project: Config,
+    files: Vec<(PathBuf, Stamp)>,
+    /// Taken from the delivery log on the first render and kept for the
+    /// session, so re-renders update that delivery rather than adding more.
+    revision: u32,
+    /// What the output template gave.
+    named: PathBuf,
+    /// Where the proof went: `named`, numbered if another delivery had the
+    /// name already. Re-renders overwrite it while `named` stays the same.
+    output: PathBuf,
+}
+
+/// Render the proof for `cli.input()`, then keep re-rendering it whenever
+/// files land, change or disappear, until `cancel` fires. Only new and
+/// changed files are processed again. Thumbnails go in `thumb_dir`.
+pub fn run(
+    cli: &Cli,
+    debounce: Duration,
+    thumb_dir: &Path,
+    cancel: &Cancel,
+    on_event: impl Fn(Event) + Sync,
+) -> Result<()> {
+    let input = cli.input();
+    anyhow::ensure!(input.is_dir(), "'{}' is not a directory", input.display());
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(input, RecursiveMode::Recursive)?;
+
+    let mut cache: HashMap<PathBuf, Cached> = HashMap::new();
+    // Thumbnails are named by this rather than list position, which shifts
+    // as files come and go.
+    let serial = AtomicUsize::new(0);
+    let mut last: Option<Snapshot> = None;
+
+    loop {
+        on_event(Event::Scanning);
+        match pass(cli, thumb_dir, cancel, &mut cache, &serial, &mut last, &on_event) {
+            Ok(()) => {}
+            Err(e) if proof::cancel::is_cancelled(&e) => return Err(e),
+            Err(e) => on_event(Event::Error(format!("{e:#}"))),
+        }
+        wait_for_changes(&rx, input, debounce, cancel)?;
+    }
+}
+
+fn pass(
+    cli: &Cli,
+    thumb_dir: &Path,
+    cancel: &Cancel,
+    cache: &mut HashMap<PathBuf, Cached>,
+    serial: &AtomicUsize,
+    last: &mut Option<Snapshot>,
+    on_event: &(impl Fn(Event) + Sync),
+) -> Result<()> {
+    let input = cli.input();
+    // Re-read every pass so edits to proof.toml apply too
+    let project = Config::load(input)?;
+    let mut found = scan::discover(input)?;
+    found.retain(|(path, _)| !project.is_excluded(input, path));
+    project.apply_order(input, &mut found);
+    on_event(Event::Found(found.clone()));
+
+    // Poster overrides change video thumbnails, so start over if they moved
+    if last.as_ref().is_some_and(|l| l.project.posters != project.posters) {
+        cache.drain().for_each(|(_, cached)| remove_thumbs(&cached.asset));
+    }
+
+    let mut opts = cli.process_options(&project);
+    opts.cancel = cancel.clone();
+    let results: Vec<(Stamp, Option<Asset>)> = found
+        .par_iter()
+        .enumerate()
+        .map(|(index, (path, kind))| {
+            let stamp = stamp(path);
+            if let Some(cached) = cache.get(path).filter(|c| c.stamp == stamp) {
+                let asset = Box::new(cached.asset.clone());
+                on_event(Event::Progress(scan::Progress::Processed { index, asset }));
+                return Ok((stamp, Some(cached.asset.clone())));
+            }
+
+            cancel.check()?;
+            on_event(Event::Progress(scan::Progress::Processing { index }));
+            let id = serial.fetch_add(1, Ordering::Relaxed);
+            match scan::process_one(path, *kind, thumb_dir, id, &opts) {
+                Ok(asset) => {
+                    let progress = scan::Progress::Processed { index, asset: Box::new(asset.clone()) };
+                    on_event(Event::Progress(progress));
+                    Ok((stamp, Some(asset)))
+                }
+                Err(ProcessError::Cancelled) => Err(ProcessError::Cancelled),
+                Err(error) => {
+                    on_event(Event::Progress(scan::Progress::Failed { index, error }));
+                    Ok((stamp, None))
+                }
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    cache.retain(|path, cached| {
+        let listed = found.iter().any(|(p, _)| p == path);
+        if !listed {
+            remove_thumbs(&cached.asset);
+        }
+        listed
+    });
+    let mut assets = Vec::new();
+    for ((path, _), (stamp, asset)) in found.iter().zip(&results) {
+        if let Some(asset) = asset {
+            let old = cache.insert(path.clone(), Cached { stamp: *stamp, asset: asset.clone() });
+            // Processed again, with thumbnails under a new serial
+            if let Some(old) = old.filter(|old| old.stamp != *stamp) {
+                remove_thumbs(&old.asset);
+            }
+            assets.push(asset.clone());
+        }
+    }
+
+    let details = cli.details(&project);
+    let revision = match last {
+        Some(last) => last.revision,
+        None => Log::load(input)?.next_revision(),
+    };
+    // Named again each pass, as `{files}` and proof.toml edits change it
+    let template = cli.template();
+    let fields = details.fields(revision, assets.len(), input);
+    let named = template.apply(&fields);
+    let output = match (&cli.output, last.as_ref()) {
+        (Some(output), _) => output.clone(),
+        (None, Some(last)) if last.named == named => last.output.clone(),
+        (None, _) => output_for(None, &template, &fields),
+    };
+    let snapshot = Snapshot {
+        project: project.clone(),
+        files: found.iter().zip(&results).map(|((path, _), (stamp, _))| (path.clone(), *stamp)).collect(),
+        revision,
+        named,
+        output: output.clone(),
+    };
+    if last.as_ref() == Some(&snapshot) {
+        on_event(Event::Unchanged);
+        return Ok(());
+    }
+    anyhow::ensure!(!assets.is_empty(), "No assets could be processed");
+
+    let mut config = pdf::PdfConfig::new(details.client, details.date);
+    config.title = details.title;
+    config.columns = cli.columns;
+    config.auto_orient = cli.auto_orient;
+    config.hero = found
+        .iter()
+        .find(|(path, _)| project.is_hero(input, path))
+        .map(|(path, _)| path.clone());
+    config.skip_duplicates = cli.skip_duplicates;
+    config.changes = match cli.previous() {
+        Some(path) => Some(changes::compare(&Previous::load(path)?, &assets, input)),
+        None => None,
+    };
+    config.revision = Some(revision);
+
+    on_event(Event::Rendering);
+    pdf::render(&assets, &config, &output, cancel)?;
+    let delivery = Delivery::new(revision, &config.client, &config.date, &assets, input, &output);
+    if let Some(message) = deliveries::record_or_warn(input, delivery) {
+        on_event(Event::Warning(message));
+    }
+    if let Some(ref dir) = cli.html {
+        proof::html::render(&assets, &config, dir, cancel)?;
+    }
+    if cli.manifest_out.is_some() {
+        cli.manifest().write(&assets, input)?;
+    }
+    // The proof this session rendered under its old name is out of date
+    if let Some(stale) = last.as_ref().map(|l| &l.output).filter(|&o| *o != output) {
+        let _ = std::fs::remove_file(stale);
+    }
+    *last = Some(snapshot);
+    let summary = Summary::new(&assets, found.len() - assets.len());
+    on_event(Event::Rendered { output, revision, summary });
+    Ok(())
+}
+
+/// Delete the thumbnail and filmstrip frames made for `asset`.
+fn remove_thumbs(asset: &Asset) {
+    let frames = asset.filmstrip.iter().map(|f| &f.path);
+    for thumb in asset.thumbnail_path.iter().chain(frames) {
+        let _ = std::fs::remove_file(thumb);
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    match std::fs::metadata(path) {
+        Ok(meta) => (meta.modified().ok(), meta.len()),
+        Err(_) => (None, 0),
+    }
+}
+
+/// Block until something relevant changes under `input`, then until it has
+/// been quiet for `debounce` so half-copied files aren't picked up.
+fn wait_for_changes(
+    rx: &mpsc::Receiver<notify::Result<notify::Event>>,
+    input: &Path,
+    debounce: Duration,
+    cancel: &Cancel,
+) -> Result<()> {
+    let poll = Duration::from_millis(200);
+    let mut changed = false;
+    loop {
+        cancel.check()?;
+        match rx.recv_timeout(if changed { debounce } else { poll }) {
+            Ok(Ok(event)) => changed |= is_relevant(&event, input),
+            Ok(Err(_)) => {}
+            Err(mpsc::RecvTimeoutError::Timeout) if changed => return Ok(()),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => anyhow::bail!("file watcher stopped"),
+        }
+    }
+}
+
+/// Asset and `proof.toml` changes count. Reads (including our own) and
+/// everything else, such as a PDF rendered into the folder, don't.
+fn is_relevant(event: &notify::Event, input: &Path) -> bool {
+    let kind = match event.kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        EventKind::Access(_) => false,
+        _ => true,
+    };
+    kind && event.paths.iter().any(|path| {
+        let hidden = config::relative(input, path).split('/').any(|c| c.starts_with('.'));
+        let settings = path.file_name().is_some_and(|n| n == config::FILENAME);
+        !hidden && (settings || scan::has_supported_extension(path))
+    })
+}
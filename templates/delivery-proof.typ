@@ -7,6 +7,10 @@
 
 #page(margin: (x: 1.5in, y: 2in))[
   #align(center + horizon)[
+    #if data.hero != none [
+      #image(data.hero, width: 100%, height: 3.2in, fit: "contain")
+      #v(2.5em)
+    ]
     #text(10pt, weight: "medium", tracking: 0.2em, fill: luma(120))[
       DELIVERY PROOF
     ]
@@ -17,7 +21,10 @@
       #text(14pt, weight: "light", fill: luma(80))[#data.title]
       #v(1em)
     ]
-    #text(11pt, fill: luma(120))[#data.date]
+    #text(11pt, fill: luma(120))[
+      #if data.revision != none [Delivery #data.revision #h(0.6em) · #h(0.6em)]
+      #data.date
+    ]
     #v(4em)
     #line(length: 2in, stroke: 0.5pt + luma(210))
     #v(1.5em)
@@ -48,6 +55,78 @@
   ],
 )
 
+// ── Changes ──────────────────────────────────────────────
+
+#let change-thumb(file) = box(
+  width: 100%,
+  height: 80pt,
+  clip: true,
+  radius: 2pt,
+  stroke: 0.5pt + luma(220),
+  fill: luma(250),
+)[
+  #align(center + horizon)[
+    #if file.thumbnail != none {
+      image(file.thumbnail, height: 80pt, fit: "contain")
+    } else {
+      text(7pt, fill: luma(160))[No preview]
+    }
+  ]
+]
+
+#let change-caption(file) = [
+  #text(7pt)[#file.name]
+  #if file.detail != "" [
+    #linebreak()
+    #text(6pt, fill: luma(140))[#file.detail]
+  ]
+]
+
+#let change-list(title, changes, name) = if changes.len() > 0 [
+  #v(1em)
+  #text(9pt, weight: "medium")[#title]
+  #h(0.4em)
+  #text(8pt, fill: luma(140))[#str(changes.len())]
+  #v(0.3em)
+  #for change in changes [
+    #text(8pt)[#name(change)]
+    #linebreak()
+  ]
+]
+
+#if data.changes != none [
+  #text(13pt, weight: "medium")[Changes since #data.changes.since]
+  #v(0.4em)
+  #text(9pt, fill: luma(100))[#upper(data.changes.summary.first())#data.changes.summary.slice(1).]
+
+  #if data.changes.replaced.len() > 0 [
+    #v(1em)
+    #text(9pt, weight: "medium")[Replaced]
+    #h(0.4em)
+    #text(8pt, fill: luma(140))[#str(data.changes.replaced.len())]
+    #v(0.5em)
+    #for change in data.changes.replaced {
+      block(breakable: false)[
+        #grid(
+          columns: (1fr, auto, 1fr),
+          column-gutter: 10pt,
+          align: (left, horizon, left),
+          [#change-thumb(change.before) #v(2pt) #change-caption(change.before)],
+          text(12pt, fill: luma(160))[→],
+          [#change-thumb(change.after) #v(2pt) #change-caption(change.after)],
+        )
+        #v(10pt)
+      ]
+    }
+  ]
+
+  #change-list("Added", data.changes.added, c => c.after.name)
+  #change-list("Removed", data.changes.removed, c => c.before.name)
+  #change-list("Renamed", data.changes.renamed, c => [#c.before.name #text(fill: luma(140))[→] #c.after.name])
+
+  #pagebreak()
+]
+
 // ── Contact Sheet ────────────────────────────────────────
 
 #text(13pt, weight: "medium")[Contact Sheet]
@@ -57,6 +136,12 @@
 
 #let cell-height = 110pt
 
+#let no-preview(asset) = if asset.unreadable {
+  text(7pt, fill: rgb("#b04040"))[Unreadable]
+} else {
+  text(7pt, fill: luma(160))[No preview]
+}
+
 #let make-cell(asset) = block(breakable: false)[
   #if data.auto_orient {
     box(
@@ -71,7 +156,7 @@
         #if asset.thumbnail != none {
           image(asset.thumbnail, height: cell-height, fit: "contain")
         } else {
-          text(7pt, fill: luma(160))[No preview]
+          no-preview(asset)
         }
       ]
     ]
@@ -86,7 +171,7 @@
         image(asset.thumbnail, width: 100%)
       } else {
         rect(width: 100%, height: 50pt, fill: luma(245))[
-          #align(center + horizon, text(7pt, fill: luma(160))[No preview])
+          #align(center + horizon, no-preview(asset))
         ]
       }
     ]
@@ -99,9 +184,41 @@
   columns: (1fr,) * cols,
   column-gutter: 8pt,
   row-gutter: 12pt,
-  ..data.assets.map(make-cell)
+  ..data.assets.filter(asset => asset.duplicate_of == none).map(make-cell)
 )
 
+// ── Filmstrips ───────────────────────────────────────────
+
+#let stripped = data.assets.filter(asset => asset.filmstrip.len() > 0)
+
+#if stripped.len() > 0 [
+  #pagebreak()
+
+  #text(13pt, weight: "medium")[Filmstrips]
+  #v(0.8em)
+
+  #for asset in stripped {
+    block(breakable: false)[
+      #text(8pt, weight: "medium")[#asset.filename]
+      #h(0.6em)
+      #text(7pt, fill: luma(140))[#asset.duration]
+      #v(4pt)
+      #grid(
+        columns: (1fr,) * asset.filmstrip.len(),
+        column-gutter: 3pt,
+        ..asset.filmstrip.map(frame => [
+          #box(width: 100%, clip: true, radius: 1pt, stroke: 0.5pt + luma(220))[
+            #image(frame.image, width: 100%)
+          ]
+          #v(2pt)
+          #text(6pt, fill: luma(120))[#frame.timecode]
+        ])
+      )
+      #v(14pt)
+    ]
+  }
+]
+
 // ── Manifest ─────────────────────────────────────────────
 
 #pagebreak()
@@ -122,7 +239,7 @@
     text(weight: "semibold", size: 8pt)[Size],
   ),
   ..data.assets.map(asset => (
-    text(size: 8pt)[#asset.filename],
+    text(size: 8pt)[#asset.filename#if asset.unreadable [ #text(fill: rgb("#b04040"))[(unreadable)]]],
     text(size: 8pt, fill: luma(80))[#asset.kind],
     text(size: 8pt)[#asset.resolution],
     text(size: 8pt, fill: luma(80))[#asset.format],
@@ -147,4 +264,99 @@
     #h(0.5em) · #h(0.5em)
     #str(data.summary.video_count) videos
   ]
+  #if data.summary.failures.len() > 0 [
+    #h(0.5em) · #h(0.5em)
+    #text(fill: rgb("#b04040"))[
+      #data.summary.failures.map(f => f.count).sum() unreadable
+      (#data.summary.failures.map(f => str(f.count) + " " + f.label).join(", "))
+    ]
+  ]
+]
+
+// ── Integrity Check ──────────────────────────────────────
+
+#if data.qc != none [
+  #v(2.5em)
+  #text(13pt, weight: "medium")[Integrity Check]
+  #v(0.8em)
+
+  #if data.qc.issues.len() == 0 [
+    #text(9pt, fill: luma(100))[
+      All #str(data.qc.checked) files decoded in full without errors.
+    ]
+  ] else [
+    #text(9pt, fill: luma(100))[
+      #str(data.qc.checked) files decoded in full.
+      #if data.qc.problems > 0 [
+        #text(fill: rgb("#b04040"))[#str(data.qc.problems) with problems.]
+      ]
+      #if data.qc.unchecked > 0 [
+        #str(data.qc.unchecked) couldn't be checked.
+      ]
+    ]
+    #v(0.6em)
+    #table(
+      columns: (auto, auto, 1fr),
+      stroke: none,
+      inset: (x: 8pt, y: 5pt),
+      fill: (_, row) => if row == 0 { luma(240) } else if calc.odd(row) { luma(248) } else { white },
+      table.header(
+        text(weight: "semibold", size: 8pt)[Filename],
+        text(weight: "semibold", size: 8pt)[Issue],
+        text(weight: "semibold", size: 8pt)[Details],
+      ),
+      ..data.qc.issues.map(issue => (
+        text(size: 8pt)[#issue.filename],
+        text(size: 8pt, fill: if issue.problem { rgb("#b04040") } else { luma(120) })[#issue.label],
+        text(size: 8pt, fill: luma(80))[#issue.message],
+      )).flatten()
+    )
+  ]
+]
+
+// ── Duplicates ───────────────────────────────────────────
+
+#if data.duplicates.len() > 0 [
+  #pagebreak()
+
+  #text(13pt, weight: "medium")[Duplicates]
+  #v(0.4em)
+  #text(8pt, fill: luma(120))[
+    Files that are the same picture: identical copies, or the same image
+    exported at another size or quality.
+  ]
+  #v(0.8em)
+
+  #for group in data.duplicates {
+    block(breakable: false)[
+      #text(8pt, weight: "medium")[#upper(group.label.first())#group.label.slice(1)]
+      #h(0.6em)
+      #text(7pt, fill: luma(140))[#str(group.members.len()) files]
+      #v(4pt)
+      #grid(
+        columns: (1fr,) * 6,
+        column-gutter: 6pt,
+        row-gutter: 8pt,
+        ..group.members.map(member => [
+          #box(width: 100%, height: 60pt, clip: true, radius: 1pt, stroke: 0.5pt + luma(220), fill: luma(250))[
+            #align(center + horizon)[
+              #if member.thumbnail != none {
+                image(member.thumbnail, height: 60pt, fit: "contain")
+              } else {
+                text(6pt, fill: luma(160))[No preview]
+              }
+            ]
+          ]
+          #v(2pt)
+          #text(6pt, fill: luma(80))[#member.filename]
+          #linebreak()
+          #text(6pt, fill: luma(140))[
+            #member.resolution · #member.human_size
+            #if member.skipped [ · not on contact sheet]
+          ]
+        ])
+      )
+      #v(14pt)
+    ]
+  }
 ]